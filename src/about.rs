@@ -0,0 +1,578 @@
+use std::env;
+use std::fs;
+
+use crate::bookmarks::Bookmark;
+use crate::cache::Cache;
+use crate::cookies::{Cookie, CookieJar};
+use crate::download::save_path;
+use crate::encoding;
+use crate::history::History;
+use crate::i18n::{tr, tr_with};
+use crate::{percent_decode, Response, USER_AGENT};
+
+/// Pages answered under `about:`
+pub const PAGES: [&str; 6] = ["blank", "cache", "config", "cookies", "newtab", "version"];
+
+/// Top sites listed on the new tab page
+const TOP_SITES: usize = 8;
+
+/// `&`, `<` and `>` as references, so URLs and header values read as they are
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `42s`, `5m`, `3h` or `2d`, rounded down to the largest unit
+fn duration(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86_400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86_400),
+    }
+}
+
+/// The value of `name` in a query such as `purge=0123abcd`
+fn param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Escapes all but unreserved characters, so a cookie name or path survives a query
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn page(title: &str, body: &str) -> Response {
+    let mut response = Response::internal_page(
+        title,
+        format!("<html><body>\n<h1>{title}</h1>\n{body}</body></html>"),
+    );
+
+    response.status_code = 200;
+
+    response
+}
+
+/// The URL to start on without one, from `--home` or `VOY_HOME`: `blank`, `newtab` or any URL
+pub fn home(setting: Option<&str>) -> String {
+    match setting {
+        None | Some("newtab") => "about:newtab".to_string(),
+        Some("blank") => "about:blank".to_string(),
+        Some(url) => url.to_string(),
+    }
+}
+
+/// `about:blank`, an empty page
+pub fn blank() -> Response {
+    let mut response = Response::internal_page("", String::new());
+
+    response.status_code = 200;
+
+    response
+}
+
+/// `about:newtab`, the most frecent pages and the bookmarks
+pub fn newtab(history: &History, bookmarks: &[Bookmark]) -> Response {
+    let mut body = format!("<h2>{}</h2>\n", tr("newtab-top-sites"));
+
+    let top_sites = history.top_sites(TOP_SITES);
+
+    if top_sites.is_empty() {
+        body.push_str(&format!("<p>{}</p>\n", tr("newtab-no-history")));
+    }
+
+    for url in top_sites {
+        body.push_str(&format!("<p>{}</p>\n", escape(url)));
+    }
+
+    body.push_str(&format!("<h2>{}</h2>\n", tr("newtab-bookmarks")));
+
+    if bookmarks.is_empty() {
+        let path = crate::bookmarks::default_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "bookmarks.txt".to_string());
+
+        body.push_str(&format!(
+            "<p>{}</p>\n",
+            escape(&tr_with("newtab-no-bookmarks", &[("path", &path)]))
+        ));
+    }
+
+    for bookmark in bookmarks {
+        let line = match &bookmark.title {
+            Some(title) => format!("{title}: {}", bookmark.url),
+            None => bookmark.url.clone(),
+        };
+
+        body.push_str(&format!("<p>{}</p>\n", escape(&line)));
+    }
+
+    page(&tr("newtab-title"), &body)
+}
+
+/// A setting or fact and its value, e.g. `Version` and `0.1.0`
+pub type Row = (String, String);
+
+/// `name: value` lines, one paragraph each
+fn rows(rows: &[Row]) -> String {
+    rows.iter()
+        .map(|(name, value)| format!("<p>{}: {}</p>\n", escape(name), escape(value)))
+        .collect()
+}
+
+/// `about:version`, what this build is and what it runs on
+pub fn version() -> Response {
+    let body = rows(&[
+        (tr("version-version"), env!("CARGO_PKG_VERSION").to_string()),
+        (
+            tr("version-platform"),
+            format!("{} {}", env::consts::OS, env::consts::ARCH),
+        ),
+        (tr("version-user-agent"), USER_AGENT.to_string()),
+        (
+            tr("version-encodings"),
+            encoding::ACCEPT_ENCODING.to_string(),
+        ),
+        (tr("version-protocols"), tr("version-protocol-list")),
+    ]);
+
+    page(&tr("version-title"), &body)
+}
+
+/// `about:config`, the settings in effect, each under the flag that changes it, and the files
+/// kept between runs
+pub fn config(settings: &[Row], files: &[Row]) -> Response {
+    let body = format!(
+        "<h2>{}</h2>\n{}<h2>{}</h2>\n{}",
+        tr("config-settings"),
+        rows(settings),
+        tr("config-files"),
+        rows(files)
+    );
+
+    page(&tr("config-title"), &body)
+}
+
+/// `about:` followed by a page we do not have
+pub fn unknown(name: &str) -> Response {
+    let pages = PAGES
+        .iter()
+        .map(|page| format!("about:{page}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    page(
+        &tr("about-unknown-title"),
+        &format!(
+            "<p>{}</p>\n",
+            tr_with(
+                "about-unknown",
+                &[("page", &escape(name)), ("pages", &pages)]
+            )
+        ),
+    )
+}
+
+/// `about:cache`, listing the disk cache
+///
+/// `?purge=<id>` drops an entry and `?save=<id>` copies its body to the working directory,
+/// with ids as listed on the page
+pub fn cache(cache: Option<&Cache>, query: &str) -> Response {
+    let title = tr("cache-title");
+
+    let Some(cache) = cache else {
+        return page(&title, &format!("<p>{}</p>\n", tr("cache-disabled")));
+    };
+
+    let mut body = String::new();
+
+    if let Some(id) = param(query, "purge") {
+        let notice = match cache.entry(id) {
+            Some((url, _)) if cache.remove(id) => tr_with("cache-purged", &[("url", &url)]),
+            _ => tr_with("cache-unknown-entry", &[("id", id)]),
+        };
+
+        body.push_str(&format!("<p>{}</p>\n", escape(&notice)));
+    }
+
+    if let Some(id) = param(query, "save") {
+        let notice = match cache.entry(id) {
+            Some((url, entry)) => {
+                let path = save_path(&url);
+
+                match fs::write(&path, entry.body()) {
+                    Ok(()) => tr_with(
+                        "cache-saved",
+                        &[("url", &url), ("path", &path.display().to_string())],
+                    ),
+                    Err(err) => tr_with(
+                        "cache-save-failed",
+                        &[("url", &url), ("error", &err.to_string())],
+                    ),
+                }
+            }
+            None => tr_with("cache-unknown-entry", &[("id", id)]),
+        };
+
+        body.push_str(&format!("<p>{}</p>\n", escape(&notice)));
+    }
+
+    let entries = cache.entries();
+
+    let dir = cache.dir().display().to_string();
+
+    if entries.is_empty() {
+        body.push_str(&format!(
+            "<p>{}</p>\n",
+            escape(&tr_with("cache-empty", &[("dir", &dir)]))
+        ));
+
+        return page(&title, &body);
+    }
+
+    body.push_str(&format!(
+        "<p>{}</p>\n",
+        escape(&tr_with(
+            "cache-summary",
+            &[("count", &entries.len().to_string()), ("dir", &dir)]
+        ))
+    ));
+
+    for (id, url, entry) in entries {
+        let freshness = match entry.expires_in() {
+            0 => tr("cache-stale"),
+            left => tr_with("cache-fresh", &[("time", &duration(left))]),
+        };
+
+        let summary = tr_with(
+            "cache-entry",
+            &[
+                ("size", &entry.body().len().to_string()),
+                ("age", &duration(entry.age())),
+                ("freshness", &freshness),
+            ],
+        );
+
+        body.push_str(&format!("<h2>{}</h2>\n<p>{summary}</p>\n", escape(&url)));
+
+        let validators = entry
+            .validators()
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>();
+
+        if !validators.is_empty() {
+            body.push_str(&format!(
+                "<p>{}</p>\n",
+                escape(&tr_with(
+                    "cache-validators",
+                    &[("validators", &validators.join(", "))]
+                ))
+            ));
+        }
+
+        body.push_str(&format!(
+            "<p>{}</p>\n",
+            tr_with(
+                "cache-actions",
+                &[
+                    ("purge", &format!("about:cache?purge={id}")),
+                    ("save", &format!("about:cache?save={id}")),
+                ],
+            )
+        ));
+    }
+
+    page(&title, &body)
+}
+
+/// `Secure`, `HttpOnly`, the path and when a cookie expires, as listed under its name
+fn attributes(cookie: &Cookie, now: u64) -> String {
+    let mut attributes = vec![tr_with("cookies-path", &[("path", &cookie.path)])];
+
+    attributes.push(match cookie.expires {
+        Some(expires) => tr_with(
+            "cookies-expires",
+            &[("time", &duration(expires.saturating_sub(now)))],
+        ),
+        None => tr("cookies-session"),
+    });
+
+    if !cookie.host_only {
+        attributes.push(tr("cookies-subdomains"));
+    }
+
+    if cookie.secure {
+        attributes.push("Secure".to_string());
+    }
+
+    if cookie.http_only {
+        attributes.push("HttpOnly".to_string());
+    }
+
+    attributes.join(", ")
+}
+
+/// `about:cookies`, listing the cookie jar by domain
+///
+/// `?q=<text>` keeps the domains and names containing it, `?delete=<name>&domain=<domain>&path=<path>`
+/// drops one cookie and `?forget=<domain>` every cookie of a site, its subdomains included
+pub fn cookies(jar: &mut CookieJar, query: &str) -> Response {
+    let title = tr("cookies-title");
+
+    let decoded = |name| param(query, name).map(percent_decode);
+
+    let mut body = String::new();
+
+    if let Some(name) = decoded("delete") {
+        let domain = decoded("domain").unwrap_or_default();
+        let path = decoded("path").unwrap_or_else(|| "/".to_string());
+
+        let notice = match jar.remove(&domain, &path, &name) {
+            true => tr_with("cookies-deleted", &[("name", &name), ("domain", &domain)]),
+            false => tr_with("cookies-unknown", &[("name", &name), ("domain", &domain)]),
+        };
+
+        body.push_str(&format!("<p>{}</p>\n", escape(&notice)));
+    }
+
+    if let Some(site) = decoded("forget") {
+        let count = jar.remove_site(&site);
+
+        body.push_str(&format!(
+            "<p>{}</p>\n",
+            escape(&tr_with(
+                "cookies-forgotten",
+                &[("domain", &site), ("count", &count.to_string())]
+            ))
+        ));
+    }
+
+    let search = decoded("q").unwrap_or_default().to_lowercase();
+
+    let cookies = jar
+        .cookies()
+        .into_iter()
+        .filter(|cookie| {
+            cookie.domain.contains(&search) || cookie.name.to_lowercase().contains(&search)
+        })
+        .collect::<Vec<_>>();
+
+    let summary = match (search.is_empty(), cookies.is_empty()) {
+        (true, true) => tr("cookies-empty"),
+        (false, true) => tr_with("cookies-no-match", &[("query", &search)]),
+        (true, false) => tr_with("cookies-summary", &[("count", &cookies.len().to_string())]),
+        (false, false) => tr_with(
+            "cookies-matching",
+            &[("query", &search), ("count", &cookies.len().to_string())],
+        ),
+    };
+
+    body.push_str(&format!("<p>{}</p>\n", escape(&summary)));
+
+    let now = crate::store::now();
+
+    let mut domain = None;
+
+    for cookie in cookies {
+        if domain != Some(&cookie.domain) {
+            domain = Some(&cookie.domain);
+
+            body.push_str(&format!(
+                "<h2>{}</h2>\n<p>{}</p>\n",
+                escape(&cookie.domain),
+                tr_with(
+                    "cookies-site-actions",
+                    &[(
+                        "forget",
+                        &format!("about:cookies?forget={}", percent_encode(&cookie.domain))
+                    )]
+                )
+            ));
+        }
+
+        body.push_str(&format!(
+            "<p>{}={}<br>\n{}<br>\n{}</p>\n",
+            escape(&cookie.name),
+            escape(&cookie.value),
+            escape(&attributes(cookie, now)),
+            tr_with(
+                "cookies-actions",
+                &[(
+                    "delete",
+                    &format!(
+                        "about:cookies?delete={}&amp;domain={}&amp;path={}",
+                        percent_encode(&cookie.name),
+                        percent_encode(&cookie.domain),
+                        percent_encode(&cookie.path)
+                    )
+                )]
+            )
+        ));
+    }
+
+    page(&title, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn list_and_purge_the_cache() {
+        let dir = env::temp_dir().join(format!("voy-about-cache-{}", std::process::id()));
+
+        let cache = Cache::new(dir.clone());
+
+        let mut response = Response::internal_page("OK", "<p>Hi</p>".to_string());
+
+        response.status_code = 200;
+        response
+            .headers
+            .insert("cache-control".to_string(), "max-age=5400".to_string());
+        response
+            .headers
+            .insert("etag".to_string(), "\"v1\"".to_string());
+
+        cache
+            .store("https://example.org/?a=1&b=2", &response)
+            .unwrap();
+
+        let listing = super::cache(Some(&cache), "").show();
+
+        assert!(listing.contains("https://example.org/?a=1&b=2"));
+        assert!(listing.contains("9 bytes, stored 0s ago, fresh for another 1h"));
+        assert!(listing.contains("If-None-Match: \"v1\""));
+
+        let (id, _, _) = cache.entries().remove(0);
+
+        let purged = super::cache(Some(&cache), &format!("purge={id}")).show();
+
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(purged.contains("Removed https://example.org/?a=1&b=2 from the cache"));
+        assert!(cache.entries().is_empty());
+
+        assert!(super::cache(Some(&cache), "purge=../../etc")
+            .show()
+            .contains("Nothing is cached under ../../etc"));
+    }
+
+    #[test]
+    fn list_search_and_delete_cookies() {
+        let url = crate::Url::new("https://www.example.org/account/login").unwrap();
+
+        let mut jar = CookieJar::default();
+
+        for set_cookie in [
+            "session=abc; Path=/; Secure; HttpOnly",
+            "theme=dark; Domain=example.org; Path=/; Max-Age=5400",
+            "a b=1; Path=/account",
+        ] {
+            jar.insert(Cookie::parse(set_cookie, &url).unwrap());
+        }
+
+        jar.insert(
+            Cookie::parse(
+                "id=7; Path=/",
+                &crate::Url::new("https://ads.example/").unwrap(),
+            )
+            .unwrap(),
+        );
+
+        let listing = cookies(&mut jar, "").show();
+
+        assert!(listing.contains("Cookies stored: 4"));
+        assert!(listing.contains("session=abc\npath /, session only, Secure, HttpOnly"));
+        assert!(listing.contains("theme=dark\npath /, expires in 1h, sent to subdomains"));
+        assert!(
+            listing.contains("about:cookies?delete=a%20b&domain=www.example.org&path=%2Faccount")
+        );
+        assert!(listing.contains("about:cookies?forget=ads.example"));
+
+        let found = cookies(&mut jar, "q=THEME").show();
+
+        assert!(found.contains("Cookies matching theme: 1"));
+        assert!(!found.contains("session=abc"));
+
+        let deleted = cookies(
+            &mut jar,
+            "delete=a%20b&domain=www.example.org&path=%2Faccount",
+        )
+        .show();
+
+        assert!(deleted.contains("Deleted the cookie a b of www.example.org"));
+        assert!(deleted.contains("Cookies stored: 3"));
+
+        let forgotten = cookies(&mut jar, "forget=example.org").show();
+
+        assert!(forgotten.contains("Cookies deleted for example.org: 2"));
+        assert_eq!(jar.cookies().len(), 1);
+    }
+
+    #[test]
+    fn list_top_sites_and_bookmarks() {
+        let mut history = History::default();
+
+        for url in [
+            "https://news.example/",
+            "https://docs.example/",
+            "https://news.example/",
+        ] {
+            history.record(&crate::Url::new(url).unwrap());
+        }
+
+        let bookmarks = [Bookmark {
+            url: "https://doc.rust-lang.org/std/".to_string(),
+            title: Some("Rust & std".to_string()),
+        }];
+
+        let shown = newtab(&history, &bookmarks).show();
+
+        let news = shown.find("https://news.example/").unwrap();
+        let docs = shown.find("https://docs.example/").unwrap();
+
+        assert!(news < docs);
+        assert!(shown.contains("Rust & std: https://doc.rust-lang.org/std/"));
+
+        let empty = newtab(&History::default(), &[]).show();
+
+        assert!(empty.contains("Pages you visit often are listed here"));
+
+        assert_eq!(home(None), "about:newtab");
+        assert_eq!(home(Some("blank")), "about:blank");
+        assert_eq!(home(Some("example.org")), "example.org");
+    }
+
+    #[test]
+    fn show_version_and_config() {
+        let version = version().text().into_owned();
+
+        assert!(version.contains(&format!("Version: {}", env!("CARGO_PKG_VERSION"))));
+        assert!(version.contains("User agent: BrowserVoy"));
+
+        let config = config(
+            &[("--header".to_string(), "X-A: <b>".to_string())],
+            &[("Cookie jar".to_string(), "none".to_string())],
+        )
+        .text()
+        .into_owned();
+
+        assert!(config.contains("<p>--header: X-A: &lt;b&gt;</p>"));
+        assert!(config.contains("<h2>Files</h2>\n<p>Cookie jar: none</p>"));
+        assert!(unknown("nope").text().contains("about:config"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::connection::Connection;
+use crate::{Response, Scheme, Url};
+
+/// How long an alternative holds when `Alt-Svc` gives no `ma`
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Another endpoint serving the same origin, e.g. `h2` on `alt.example.org:443`
+#[derive(Debug, PartialEq)]
+pub struct Alternative {
+    /// The ALPN protocol id, e.g. `h2` or `h3`
+    pub protocol: String,
+    /// `host:port` to connect to, while TLS and the Host header still name the origin
+    pub host: String,
+    pub expires: Instant,
+}
+
+/// Alternatives advertised through `Alt-Svc`, keyed by `Connection::key`
+#[derive(Default)]
+pub struct AltSvc {
+    origins: HashMap<String, Vec<Alternative>>,
+}
+
+/// The alternatives of an `Alt-Svc` value, or `None` for `clear`
+fn parse(header: &str, url: &Url, now: Instant) -> Option<Vec<Alternative>> {
+    if header.trim() == "clear" {
+        return None;
+    }
+
+    let mut alternatives = vec![];
+
+    for entry in header.split(',') {
+        let mut params = entry.split(';').map(str::trim);
+
+        let Some((protocol, authority)) = params.next().and_then(|first| first.split_once('='))
+        else {
+            continue;
+        };
+
+        let authority = authority.trim().trim_matches('"');
+
+        // `:443` keeps the origin's hostname
+        let host = match authority.strip_prefix(':') {
+            Some(port) => format!("{}:{port}", url.host.rsplit_once(':')?.0),
+            None => authority.to_string(),
+        };
+
+        let max_age = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim() == "ma")
+            .and_then(|(_, seconds)| seconds.trim().trim_matches('"').parse().ok())
+            .map_or(DEFAULT_MAX_AGE, Duration::from_secs);
+
+        alternatives.push(Alternative {
+            protocol: protocol.trim().to_string(),
+            host,
+            expires: now + max_age,
+        });
+    }
+
+    Some(alternatives)
+}
+
+impl AltSvc {
+    /// Remembers what a response advertises; only HTTPS origins are taken up on it
+    pub fn store(&mut self, url: &Url, response: &Response) {
+        if url.scheme != Scheme::Https {
+            return;
+        }
+
+        let Some(header) = response.headers.get("alt-svc") else {
+            return;
+        };
+
+        let key = Connection::key(url);
+
+        match parse(header, url, Instant::now()) {
+            Some(alternatives) => self.origins.insert(key, alternatives),
+            None => self.origins.remove(&key),
+        };
+    }
+
+    /// The endpoint to connect to instead of the origin, among the protocols we speak
+    pub fn lookup(&self, url: &Url, protocols: &[&str]) -> Option<&str> {
+        let now = Instant::now();
+
+        self.origins
+            .get(&Connection::key(url))?
+            .iter()
+            .filter(|alternative| alternative.expires > now)
+            .find(|alternative| protocols.contains(&alternative.protocol.as_str()))
+            .map(|alternative| alternative.host.as_str())
+    }
+
+    /// Stops using the origin's alternatives, e.g. after one could not be reached
+    pub fn forget(&mut self, url: &Url) {
+        self.origins.remove(&Connection::key(url));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advertising(header: &str) -> Response {
+        let mut response = Response::internal_page("OK", String::new());
+
+        response
+            .headers
+            .insert("alt-svc".to_string(), header.to_string());
+
+        response
+    }
+
+    #[test]
+    fn remember_alternatives() {
+        let url = Url::new("https://example.org/").unwrap();
+
+        let mut alt_svc = AltSvc::default();
+
+        alt_svc.store(
+            &url,
+            &advertising("h3=\":443\"; ma=86400, h2=\"alt.example.org:8443\"; ma=60"),
+        );
+
+        assert_eq!(alt_svc.lookup(&url, &["h2"]), Some("alt.example.org:8443"));
+        assert_eq!(alt_svc.lookup(&url, &["h3"]), Some("example.org:443"));
+        assert_eq!(alt_svc.lookup(&url, &["http/1.1"]), None);
+        assert_eq!(
+            alt_svc.lookup(&Url::new("https://example.org:8443/").unwrap(), &["h2"]),
+            None
+        );
+
+        alt_svc.store(&url, &advertising("h2=\"alt.example.org:443\"; ma=0"));
+
+        assert_eq!(alt_svc.lookup(&url, &["h2"]), None);
+
+        alt_svc.store(&url, &advertising("h2=\":443\""));
+        alt_svc.store(&url, &advertising("clear"));
+
+        assert_eq!(alt_svc.lookup(&url, &["h2"]), None);
+
+        // Plain HTTP responses could be forged by anyone on the path
+        let plain = Url::new("http://example.org/").unwrap();
+
+        alt_svc.store(&plain, &advertising("h2=\":443\""));
+
+        assert_eq!(alt_svc.lookup(&plain, &["h2"]), None);
+    }
+}
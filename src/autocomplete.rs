@@ -0,0 +1,172 @@
+use crate::bookmarks::Bookmark;
+use crate::history::History;
+use crate::suggest;
+
+/// Where a suggestion came from, shown next to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    History,
+    Bookmark,
+    /// `input` with a typo fixed, e.g. `example.con` as `example.com`
+    Correction,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Suggestion {
+    pub url: String,
+    pub title: Option<String>,
+    pub source: Source,
+}
+
+/// `https://www.Example.org/a` as `example.org/a`, the part people type
+fn typed_form(url: &str) -> String {
+    let url = url.to_lowercase();
+
+    let url = url
+        .split_once("://")
+        .map_or(url.as_str(), |(_, rest)| rest)
+        .to_string();
+
+    url.strip_prefix("www.").map(str::to_string).unwrap_or(url)
+}
+
+/// Up to `limit` URLs for a partly typed `input`, best first
+///
+/// Pages from history and bookmarks whose address starts with the input come first, history
+/// ranked by frecency, then those containing it anywhere, bookmark titles included, and last the
+/// input with a mistyped hostname corrected.
+pub fn suggest(
+    input: &str,
+    history: &History,
+    bookmarks: &[Bookmark],
+    limit: usize,
+) -> Vec<Suggestion> {
+    let typed = typed_form(input.trim());
+
+    if typed.is_empty() {
+        return vec![];
+    }
+
+    let visited = history
+        .top_sites(usize::MAX)
+        .into_iter()
+        .map(|url| Suggestion {
+            url: url.to_string(),
+            title: None,
+            source: Source::History,
+        });
+
+    let saved = bookmarks.iter().map(|bookmark| Suggestion {
+        url: bookmark.url.clone(),
+        title: bookmark.title.clone(),
+        source: Source::Bookmark,
+    });
+
+    let (prefixed, others): (Vec<_>, Vec<_>) = visited
+        .chain(saved)
+        .partition(|suggestion| typed_form(&suggestion.url).starts_with(&typed));
+
+    let containing = others.into_iter().filter(|suggestion| {
+        typed_form(&suggestion.url).contains(&typed)
+            || suggestion
+                .title
+                .as_ref()
+                .is_some_and(|title| title.to_lowercase().contains(&typed))
+    });
+
+    let mut suggestions: Vec<Suggestion> = vec![];
+
+    for suggestion in prefixed.into_iter().chain(containing) {
+        if !suggestions.iter().any(|known| known.url == suggestion.url) {
+            suggestions.push(suggestion);
+        }
+    }
+
+    let (host, path) = typed.split_once('/').unwrap_or((&typed, ""));
+
+    // A single word is more likely part of a page than a hostname short of its domain
+    let hostname_like = host.contains('.') && !host.contains(char::is_whitespace);
+
+    if let Some(host) = suggest::hostname(host).filter(|_| hostname_like) {
+        let url = match path {
+            "" => format!("https://{host}/"),
+            path => format!("https://{host}/{path}"),
+        };
+
+        if !suggestions.iter().any(|known| known.url == url) {
+            suggestions.push(Suggestion {
+                url,
+                title: None,
+                source: Source::Correction,
+            });
+        }
+    }
+
+    suggestions.truncate(limit);
+
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Url;
+
+    #[test]
+    fn merge_sources() {
+        let mut history = History::default();
+
+        for url in [
+            "https://www.rust-lang.org/learn",
+            "https://docs.rs/",
+            "https://www.rust-lang.org/learn",
+            "https://blog.rust-lang.org/",
+        ] {
+            history.record(&Url::new(url).unwrap());
+        }
+
+        let bookmarks = [
+            Bookmark {
+                url: "https://doc.rust-lang.org/std/".to_string(),
+                title: Some("Rust standard library".to_string()),
+            },
+            Bookmark {
+                url: "https://docs.rs/".to_string(),
+                title: Some("Docs.rs".to_string()),
+            },
+        ];
+
+        let urls = |input| {
+            suggest(input, &history, &bookmarks, 5)
+                .into_iter()
+                .map(|suggestion| suggestion.url)
+                .collect::<Vec<_>>()
+        };
+
+        // Visited twice beats visited once, and `www.` is not needed to match
+        assert_eq!(
+            urls("rust"),
+            [
+                "https://www.rust-lang.org/learn",
+                "https://blog.rust-lang.org/",
+                "https://doc.rust-lang.org/std/",
+            ]
+        );
+
+        // Known from both history and bookmarks, listed once
+        assert_eq!(urls("DOCS"), ["https://docs.rs/"]);
+
+        assert_eq!(urls("standard"), ["https://doc.rust-lang.org/std/"]);
+
+        assert_eq!(
+            suggest("example.con/a", &history, &bookmarks, 5),
+            [Suggestion {
+                url: "https://example.com/a".to_string(),
+                title: None,
+                source: Source::Correction,
+            }]
+        );
+
+        assert!(urls("  ").is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding (RFC 4648)
+pub fn encode(input: &[u8]) -> String {
+    let mut encoded = String::new();
+
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+
+        let group = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(char::from(
+                    ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize],
+                ));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// The bytes behind standard or URL-safe base64, with or without padding
+///
+/// Whitespace is skipped, as in data URLs wrapped over several lines; anything else outside
+/// the alphabet makes the input invalid.
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    let mut decoded = vec![];
+
+    let mut group = 0u32;
+    let mut bits = 0;
+
+    let digits = input
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+
+    let unpadded = match digits.iter().position(|&byte| byte == b'=') {
+        // Padding only ever closes the input
+        Some(start) if digits[start..].iter().all(|&byte| byte == b'=') => &digits[..start],
+        Some(_) => return None,
+        None => &digits[..],
+    };
+
+    for &byte in unpadded {
+        let value = match byte {
+            b'-' => 62,
+            b'_' => 63,
+            byte => ALPHABET.iter().position(|&digit| digit == byte)? as u32,
+        };
+
+        group = group << 6 | value;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+
+            decoded.push((group >> bits) as u8);
+
+            group &= (1 << bits) - 1;
+        }
+    }
+
+    // A single digit past a full group cannot make up a byte
+    if unpadded.len() % 4 == 1 {
+        return None;
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).unwrap(), bytes);
+        }
+
+        assert_eq!(decode("Zm9v\n YmE").unwrap(), b"fooba");
+        assert_eq!(decode("-_8=").unwrap(), [0xfb, 0xff]);
+
+        assert!(decode("Zm9v!").is_none());
+        assert!(decode("Zg==Zg").is_none());
+        assert!(decode("Zm9vY").is_none());
+    }
+}
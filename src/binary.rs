@@ -0,0 +1,125 @@
+use crate::charset::Charset;
+
+/// Bytes looked at to tell text from binary when the media type does not say
+const SNIFF_BYTES: usize = 1024;
+
+/// Bytes of a binary body shown in its preview
+pub const PREVIEW_BYTES: usize = 256;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Media types outside `text/` that still read as text
+fn is_textual(media_type: &str) -> bool {
+    media_type.starts_with("text/")
+        || media_type.ends_with("+xml")
+        || media_type.ends_with("+json")
+        || matches!(
+            media_type,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/ecmascript"
+                | "application/x-www-form-urlencoded"
+        )
+}
+
+/// Whether a body would come out as garbage on a terminal, e.g. an image or an archive
+///
+/// A declared media type is trusted, save for a NUL byte in text; without one, or with
+/// `application/octet-stream`, the first bytes must be UTF-8 as well.
+pub fn is_binary(content_type: Option<&str>, body: &[u8]) -> bool {
+    let media_type = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|media_type| media_type.trim().to_lowercase())
+        .unwrap_or_default();
+
+    let sample = &body[..body.len().min(SNIFF_BYTES)];
+
+    // Every other byte of UTF-16 text is NUL
+    let utf16 = content_type
+        .and_then(Charset::from_content_type)
+        .is_some_and(|charset| matches!(charset, Charset::Utf16Le | Charset::Utf16Be));
+
+    if sample.contains(&0) && !utf16 {
+        return true;
+    }
+
+    if is_textual(&media_type) {
+        return false;
+    }
+
+    match media_type.as_str() {
+        // A character cut off by the end of the sample is still text
+        "" | "application/octet-stream" => {
+            std::str::from_utf8(sample).is_err_and(|err| err.error_len().is_some())
+        }
+        _ => true,
+    }
+}
+
+/// Offsets, bytes in hex and the printable ones as text, like `hexdump -C`
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+
+    for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>();
+
+        let (first, second) = hex.split_at(hex.len().min(BYTES_PER_LINE / 2));
+
+        let text = chunk
+            .iter()
+            .map(|&byte| match byte {
+                b' '..=b'~' => byte as char,
+                _ => '.',
+            })
+            .collect::<String>();
+
+        dump.push_str(&format!(
+            "{:08x}  {:<23}  {:<23}  |{text}|\n",
+            line * BYTES_PER_LINE,
+            first.join(" "),
+            second.join(" ")
+        ));
+    }
+
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tell_binary_from_text() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+        assert!(is_binary(Some("image/png"), png));
+        assert!(is_binary(None, png));
+        assert!(is_binary(Some("application/pdf"), b"%PDF-1.7"));
+        assert!(is_binary(Some("text/plain"), b"PK\x03\x04\0\0"));
+
+        assert!(!is_binary(Some("text/html; charset=utf-8"), b"<p>Hi</p>"));
+        assert!(!is_binary(Some("image/svg+xml"), b"<svg/>"));
+        assert!(!is_binary(
+            Some("text/html; charset=iso-8859-1"),
+            b"caf\xe9"
+        ));
+        assert!(!is_binary(None, "Grüße".as_bytes()));
+        assert!(!is_binary(Some("text/plain; charset=utf-16le"), b"H\0i\0"));
+        assert!(!is_binary(Some("application/octet-stream"), b"plain"));
+        // Cut through the middle of `ü`
+        assert!(!is_binary(None, &"Grüße".as_bytes()[..3]));
+    }
+
+    #[test]
+    fn dump_hex() {
+        assert_eq!(
+            hexdump(b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\xff\xffHello"),
+            "00000000  47 49 46 38 39 61 01 00  01 00 80 00 00 ff ff ff  |GIF89a..........|\n\
+             00000010  48 65 6c 6c 6f                                    |Hello|\n"
+        );
+    }
+}
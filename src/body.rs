@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Take};
+
+use crate::HTTP_1_0;
+
+/// A response body read as it arrives, undoing its framing but not its `Content-Encoding`
+///
+/// Nothing is buffered beyond what the connection's reader holds, so a body can be handed
+/// on piece by piece, e.g. to a file, however large it is.
+pub enum BodyReader<'a, R> {
+    /// `Content-Length` bytes
+    Length(Take<&'a mut R>),
+    Chunked(Chunked<'a, R>),
+    /// Everything until the server closes the connection
+    Close(&'a mut R),
+}
+
+/// The chunks of a `Transfer-Encoding: chunked` body, then the trailers
+pub struct Chunked<'a, R> {
+    reader: &'a mut R,
+    /// Bytes of the current chunk still to read
+    left: u64,
+    /// Fields after the last chunk, with lowercase names, once it was read
+    trailers: Option<Vec<(String, String)>>,
+}
+
+impl<'a, R: BufRead> BodyReader<'a, R> {
+    /// Reads the body that follows a head with `headers`, lowercase names as keys, sent
+    /// by a server speaking `version`, e.g. `HTTP/1.0`
+    pub fn new(
+        reader: &'a mut R,
+        headers: &HashMap<String, String>,
+        version: &str,
+    ) -> io::Result<Self> {
+        // HTTP/1.0 has no transfer codings, so a length sent alongside one cannot be trusted
+        // either, and the body runs until the server closes (RFC 9112, section 6.1)
+        if version == HTTP_1_0 && headers.contains_key("transfer-encoding") {
+            return Ok(BodyReader::Close(reader));
+        }
+
+        let chunked = headers
+            .get("transfer-encoding")
+            .is_some_and(|value| value.to_lowercase().contains("chunked"));
+
+        // Chunks frame the body themselves, whatever length is also given
+        if chunked {
+            return Ok(BodyReader::Chunked(Chunked {
+                reader,
+                left: 0,
+                trailers: None,
+            }));
+        }
+
+        Ok(match headers.get("content-length") {
+            Some(length) => BodyReader::Length(reader.take(content_length(length)?)),
+            None => BodyReader::Close(reader),
+        })
+    }
+
+    /// Whether the end of the body is marked, so the connection can carry another request
+    pub fn is_framed(&self) -> bool {
+        !matches!(self, BodyReader::Close(_))
+    }
+
+    /// Bytes still expected, when the length is known up front
+    pub fn remaining(&self) -> Option<u64> {
+        match self {
+            BodyReader::Length(body) => Some(body.limit()),
+            _ => None,
+        }
+    }
+
+    /// Whether everything the framing announced was read
+    pub fn is_complete(&self) -> bool {
+        match self {
+            BodyReader::Length(body) => body.limit() == 0,
+            BodyReader::Chunked(chunked) => chunked.trailers.is_some(),
+            BodyReader::Close(_) => true,
+        }
+    }
+
+    /// The fields sent after a chunked body, once it was read to the end
+    pub fn into_trailers(self) -> Vec<(String, String)> {
+        match self {
+            BodyReader::Chunked(chunked) => chunked.trailers.unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+}
+
+impl<R: BufRead> Read for BodyReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            BodyReader::Length(body) => body.read(buf),
+            BodyReader::Chunked(chunked) => chunked.read(buf),
+            BodyReader::Close(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> Read for Chunked<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.trailers.is_some() || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.left == 0 {
+            self.left = read_chunk_size(self.reader)?;
+
+            if self.left == 0 {
+                self.trailers = Some(read_trailers(self.reader)?);
+
+                return Ok(0);
+            }
+        }
+
+        let len = buf
+            .len()
+            .min(usize::try_from(self.left).unwrap_or(usize::MAX));
+
+        let read = self.reader.read(&mut buf[..len])?;
+
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.left -= read as u64;
+
+        // Every chunk is followed by CRLF
+        if self.left == 0 {
+            self.reader.read_line(&mut String::new())?;
+        }
+
+        Ok(read)
+    }
+}
+
+/// The length of a body, from a `Content-Length` that may have been sent more than once
+///
+/// Repeats, folded into `13, 13`, must agree; guessing between lengths that do not would let
+/// the rest of the body pass for the next response.
+fn content_length(value: &str) -> io::Result<u64> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid Content-Length {value}"),
+        )
+    };
+
+    let mut lengths = value.split(',').map(|length| {
+        let length = length.trim();
+
+        match length.bytes().all(|byte| byte.is_ascii_digit()) {
+            true => length.parse::<u64>().map_err(|_| invalid()),
+            false => Err(invalid()),
+        }
+    });
+
+    let first = lengths.next().unwrap_or_else(|| Err(invalid()))?;
+
+    for length in lengths {
+        if length? != first {
+            return Err(invalid());
+        }
+    }
+
+    Ok(first)
+}
+
+fn read_chunk_size<R: BufRead>(reader: &mut R) -> io::Result<u64> {
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    // Chunk extensions follow a `;` and carry nothing we use
+    let size = line.split(';').next().unwrap_or_default().trim();
+
+    u64::from_str_radix(size, 16).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid chunk size {size}"),
+        )
+    })
+}
+
+/// Trailer fields, up to the final empty line
+fn read_trailers<R: BufRead>(reader: &mut R) -> io::Result<Vec<(String, String)>> {
+    let mut trailers = vec![];
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            trailers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    Ok(trailers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HTTP_1_1;
+
+    #[test]
+    fn read_chunks_piece_by_piece() {
+        let mut input =
+            "5\r\nHello\r\n8\r\n, world!\r\n0\r\nServer-Timing: db\r\n\r\nNEXT".as_bytes();
+
+        let headers = HashMap::from([("transfer-encoding".to_string(), "chunked".to_string())]);
+
+        let mut body = BodyReader::new(&mut input, &headers, HTTP_1_1).unwrap();
+
+        let mut pieces = vec![];
+
+        let mut buf = [0; 3];
+
+        loop {
+            match body.read(&mut buf).unwrap() {
+                0 => break,
+                read => pieces.push(String::from_utf8_lossy(&buf[..read]).into_owned()),
+            }
+        }
+
+        assert_eq!(pieces, ["Hel", "lo", ", w", "orl", "d!"]);
+        assert!(body.is_framed() && body.is_complete());
+        assert_eq!(
+            body.into_trailers(),
+            [("server-timing".to_string(), "db".to_string())]
+        );
+        assert_eq!(input, b"NEXT");
+    }
+
+    #[test]
+    fn read_http_1_0_until_close() {
+        let mut input = "5\r\nHello\r\n0\r\n\r\n".as_bytes();
+
+        let headers = HashMap::from([
+            ("transfer-encoding".to_string(), "chunked".to_string()),
+            ("content-length".to_string(), "5".to_string()),
+        ]);
+
+        let mut body = BodyReader::new(&mut input, &headers, HTTP_1_0).unwrap();
+
+        let mut bytes = vec![];
+
+        body.read_to_end(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"5\r\nHello\r\n0\r\n\r\n");
+        assert!(!body.is_framed());
+    }
+
+    #[test]
+    fn check_content_lengths() {
+        assert_eq!(content_length("13").unwrap(), 13);
+        assert_eq!(content_length(" 13 , 13").unwrap(), 13);
+
+        assert!(content_length("13, 14").is_err());
+        assert!(content_length("+13").is_err());
+        assert!(content_length("-1").is_err());
+        assert!(content_length("").is_err());
+        assert!(content_length("99999999999999999999999").is_err());
+    }
+}
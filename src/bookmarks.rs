@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store;
+
+/// A page the user saved by hand, listed on the new tab page
+#[derive(Debug, PartialEq)]
+pub struct Bookmark {
+    pub url: String,
+    /// Shown instead of the URL when given
+    pub title: Option<String>,
+}
+
+/// `$XDG_DATA_HOME/voy/bookmarks.txt`, next to the cookie jar
+pub fn default_path() -> Option<PathBuf> {
+    Some(store::data_dir()?.join("bookmarks.txt"))
+}
+
+/// Reads one bookmark per line, a URL optionally followed by a title, e.g.
+/// `https://doc.rust-lang.org/std/ Rust standard library`; `#` starts a comment
+pub fn load(path: &Path) -> Vec<Bookmark> {
+    let Ok(source) = fs::read_to_string(path) else {
+        return vec![];
+    };
+
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (url, title) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+            Bookmark {
+                url: url.to_string(),
+                title: Some(title.trim().to_string()).filter(|title| !title.is_empty()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn read_bookmarks() {
+        let path = env::temp_dir().join(format!("voy-bookmarks-{}.txt", std::process::id()));
+
+        fs::write(
+            &path,
+            "# Reading\nhttps://doc.rust-lang.org/std/ Rust standard library\n\n  example.org  \n",
+        )
+        .unwrap();
+
+        let bookmarks = load(&path);
+
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(
+            bookmarks,
+            [
+                Bookmark {
+                    url: "https://doc.rust-lang.org/std/".to_string(),
+                    title: Some("Rust standard library".to_string()),
+                },
+                Bookmark {
+                    url: "example.org".to_string(),
+                    title: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::store::{self, now};
+use crate::Response;
+
+/// Headers describing the bytes on the wire, which no longer apply once the body is decoded
+const WIRE_HEADERS: [&str; 3] = ["content-encoding", "content-length", "transfer-encoding"];
+
+/// Headers meant for the one response they came with, which a cache hit must not replay
+const PRIVATE_HEADERS: [&str; 1] = ["set-cookie"];
+
+fn is_stored(key: &str) -> bool {
+    !WIRE_HEADERS.contains(&key) && !PRIVATE_HEADERS.contains(&key)
+}
+
+/// Responses kept on disk, one file per URL, so they can be served again or revalidated
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// A stored response together with when it was stored
+pub struct Entry {
+    stored: u64,
+    response: Response,
+}
+
+/// Values of `Cache-Control`, e.g. `max-age=60` becomes `("max-age", Some("60"))`
+fn directives(response: &Response) -> Vec<(String, Option<String>)> {
+    let Some(cache_control) = response.headers.get("cache-control") else {
+        return vec![];
+    };
+
+    cache_control
+        .split(',')
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_lowercase(),
+                Some(value.trim().trim_matches('"').to_string()),
+            ),
+            None => (directive.trim().to_lowercase(), None),
+        })
+        .collect()
+}
+
+fn has_directive(response: &Response, name: &str) -> bool {
+    directives(response)
+        .iter()
+        .any(|(directive, _)| directive == name)
+}
+
+/// Whether `id` names a cache file, and nothing outside the cache directory
+fn is_id(id: &str) -> bool {
+    id.len() == 16 && id.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// A cache file, as the URL it holds and the stored response
+fn read(path: &Path) -> Option<(String, Entry)> {
+    let source = fs::read(path).ok()?;
+
+    let split = source.windows(2).position(|pair| pair == b"\n\n")?;
+
+    let head = std::str::from_utf8(&source[..split]).ok()?;
+
+    let body = source[split + 2..].to_vec();
+
+    let mut lines = head.lines();
+
+    let url = lines.next()?.to_string();
+
+    let mut status = lines.next()?.splitn(4, ' ');
+
+    let stored = status.next()?.parse().ok()?;
+    let status_code = status.next()?.parse().ok()?;
+    let version = status.next()?.to_string();
+    let explanation = status.next().unwrap_or_default().to_string();
+
+    let headers = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    Some((
+        url,
+        Entry {
+            stored,
+            response: Response {
+                version,
+                status_code,
+                explanation,
+                headers,
+                body,
+                truncated: false,
+            },
+        },
+    ))
+}
+
+// FNV-1a, which is plenty to spread URLs over file names
+fn file_name(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+
+    format!("{hash:016x}")
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// `$XDG_CACHE_HOME/voy`, or `~/.cache/voy` when that is not set
+    pub fn default_dir() -> Option<PathBuf> {
+        store::cache_dir()
+    }
+
+    /// Only complete, successful responses the server did not forbid us to keep
+    pub fn is_storable(response: &Response) -> bool {
+        response.status_code == 200 && !response.truncated && !has_directive(response, "no-store")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get(&self, url: &str) -> Option<Entry> {
+        let (stored_url, entry) = read(&self.dir.join(file_name(url)))?;
+
+        // Another URL with the same hash may have replaced the entry
+        (stored_url == url).then_some(entry)
+    }
+
+    /// Every stored response by id and URL, sorted by URL
+    pub fn entries(&self) -> Vec<(String, String, Entry)> {
+        let mut entries: Vec<_> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|file| {
+                let path = file.ok()?.path();
+
+                let id = path.file_name()?.to_str()?.to_string();
+
+                let (url, entry) = read(&path)?;
+
+                Some((id, url, entry))
+            })
+            .collect();
+
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        entries
+    }
+
+    /// The response stored under `id`, as listed by `entries`, and its URL
+    pub fn entry(&self, id: &str) -> Option<(String, Entry)> {
+        is_id(id).then(|| read(&self.dir.join(id))).flatten()
+    }
+
+    /// Drops the response stored under `id`, telling whether there was one
+    pub fn remove(&self, id: &str) -> bool {
+        is_id(id) && fs::remove_file(self.dir.join(id)).is_ok()
+    }
+
+    pub fn store(&self, url: &str, response: &Response) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut source = format!(
+            "{url}\n{stored} {status_code} {version} {explanation}\n",
+            stored = now(),
+            status_code = response.status_code,
+            version = response.version,
+            explanation = response.explanation,
+        );
+
+        for (key, value) in &response.headers {
+            if is_stored(key) {
+                source.push_str(&format!("{key}: {value}\n"));
+            }
+        }
+
+        source.push('\n');
+
+        let mut source = source.into_bytes();
+
+        source.extend(&response.body);
+
+        fs::write(self.dir.join(file_name(url)), source)
+    }
+
+    /// Updates a stored response with the headers of a `304 Not Modified`, and hands it back
+    pub fn refresh(&self, url: &str, mut entry: Entry, not_modified: &Response) -> Response {
+        for (key, value) in &not_modified.headers {
+            if is_stored(key) {
+                entry.response.headers.insert(key.clone(), value.clone());
+            }
+        }
+
+        // A cache that cannot be written only costs a refetch next time
+        let _ = self.store(url, &entry.response);
+
+        let mut response = entry.response;
+
+        // Cookies set along with the 304 still reach the jar, this once
+        if let Some(set_cookie) = not_modified.headers.get("set-cookie") {
+            response
+                .headers
+                .insert("set-cookie".to_string(), set_cookie.clone());
+        }
+
+        response
+    }
+}
+
+impl Entry {
+    /// Seconds the response stays fresh for, `None` when it must always be revalidated
+    fn max_age(&self) -> Option<u64> {
+        let mut max_age = None;
+
+        for (directive, value) in directives(&self.response) {
+            match directive.as_str() {
+                "no-cache" => return None,
+                "max-age" => max_age = value.and_then(|value| value.parse::<u64>().ok()),
+                _ => {}
+            }
+        }
+
+        max_age
+    }
+
+    /// Seconds since the response was stored
+    pub fn age(&self) -> u64 {
+        now().saturating_sub(self.stored)
+    }
+
+    /// Whether the response can be used without asking the server first
+    pub fn is_fresh(&self) -> bool {
+        self.max_age().is_some_and(|max_age| self.age() < max_age)
+    }
+
+    /// Seconds left before the response has to be revalidated, zero once stale
+    pub fn expires_in(&self) -> u64 {
+        self.max_age()
+            .map_or(0, |max_age| max_age.saturating_sub(self.age()))
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.response.body
+    }
+
+    /// `If-None-Match` and `If-Modified-Since` headers to revalidate the response with
+    pub fn validators(&self) -> Vec<(&'static str, String)> {
+        let headers = &self.response.headers;
+
+        let mut validators = vec![];
+
+        if let Some(etag) = headers.get("etag") {
+            validators.push(("If-None-Match", etag.clone()));
+        }
+
+        if let Some(last_modified) = headers.get("last-modified") {
+            validators.push(("If-Modified-Since", last_modified.clone()));
+        }
+
+        validators
+    }
+
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn response(headers: &[(&str, &str)]) -> Response {
+        Response {
+            version: "HTTP/1.1".to_string(),
+            status_code: 200,
+            explanation: "OK".to_string(),
+            headers: headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            body: b"<p>Cached\n\nbody</p>".to_vec(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn store_and_revalidate() {
+        let dir = env::temp_dir().join(format!("voy-cache-{}", std::process::id()));
+
+        let cache = Cache::new(dir.clone());
+
+        let url = "https://example.org/";
+
+        assert!(cache.get(url).is_none());
+
+        let stored = response(&[
+            ("cache-control", "max-age=3600"),
+            ("etag", "\"v1\""),
+            ("content-encoding", "gzip"),
+        ]);
+
+        assert!(Cache::is_storable(&stored));
+
+        cache.store(url, &stored).unwrap();
+
+        let entry = cache.get(url).unwrap();
+
+        assert!(entry.is_fresh());
+        assert_eq!(
+            entry.validators(),
+            [("If-None-Match", "\"v1\"".to_string())]
+        );
+
+        let not_modified = response(&[("cache-control", "no-cache")]);
+
+        let refreshed = cache.refresh(url, entry, &not_modified);
+
+        assert_eq!(refreshed.text(), "<p>Cached\n\nbody</p>");
+        assert!(!refreshed.headers.contains_key("content-encoding"));
+        assert!(!cache.get(url).unwrap().is_fresh());
+        assert!(cache.get("https://example.org/other").is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn honor_cache_control() {
+        assert!(!Cache::is_storable(&response(&[(
+            "cache-control",
+            "no-store"
+        )])));
+
+        let entry = |cache_control| Entry {
+            stored: now(),
+            response: response(&[("cache-control", cache_control)]),
+        };
+
+        assert!(entry("public, max-age=60").is_fresh());
+        assert!(!entry("max-age=0").is_fresh());
+        assert!(!entry("max-age=60, no-cache").is_fresh());
+        assert!(!entry("private").is_fresh());
+    }
+}
@@ -0,0 +1,329 @@
+use crate::cookies::days_from_civil;
+use crate::i18n::tr_with;
+use crate::store;
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_OID: u8 = 0x06;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_UTC_TIME: u8 = 0x17;
+/// `[0]`, holding the version of a v3 certificate
+const TAG_VERSION: u8 = 0xa0;
+/// `[3]`, holding the extensions
+const TAG_EXTENSIONS: u8 = 0xa3;
+/// `dNSName` and `iPAddress` in a `GeneralName`
+const TAG_DNS_NAME: u8 = 0x82;
+const TAG_IP_ADDRESS: u8 = 0x87;
+
+/// 2.5.29.17
+const OID_SUBJECT_ALT_NAME: [u8; 3] = [0x55, 0x1d, 0x11];
+
+/// Short names for the attributes of `2.5.4`, e.g. `CN` for 2.5.4.3
+const ATTRIBUTES: [(u8, &str); 7] = [
+    (3, "CN"),
+    (5, "serialNumber"),
+    (6, "C"),
+    (7, "L"),
+    (8, "ST"),
+    (10, "O"),
+    (11, "OU"),
+];
+
+/// Reads DER one tag-length-value at a time
+struct Der<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Der { bytes }
+    }
+
+    /// The tag and contents of the next value
+    fn next(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.bytes.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+
+        let length = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let count = usize::from(first & 0x7f);
+
+            if count > 4 || rest.len() < count {
+                return None;
+            }
+
+            let (length, after) = rest.split_at(count);
+
+            rest = after;
+
+            length
+                .iter()
+                .fold(0, |length, &byte| length << 8 | usize::from(byte))
+        };
+
+        if rest.len() < length {
+            return None;
+        }
+
+        let (contents, rest) = rest.split_at(length);
+
+        self.bytes = rest;
+
+        Some((tag, contents))
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<&'a [u8]> {
+        self.next()
+            .and_then(|(tag, contents)| (tag == expected).then_some(contents))
+    }
+}
+
+/// A point in time as written in the certificate, and as seconds since the Unix epoch
+#[derive(Debug, PartialEq)]
+pub struct Time {
+    pub text: String,
+    pub epoch: u64,
+}
+
+impl Time {
+    /// `YYMMDDHHMMSSZ` for UTCTime, `YYYYMMDDHHMMSSZ` for GeneralizedTime
+    fn parse(tag: u8, contents: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(contents).ok()?;
+
+        let text = text.strip_suffix('Z')?;
+
+        let (year, rest) = if tag == TAG_UTC_TIME {
+            let (year, rest) = text.split_at_checked(2)?;
+
+            let year = year.parse::<i64>().ok()?;
+
+            // RFC 5280: two-digit years from 50 are in the 1900s
+            (if year >= 50 { 1900 + year } else { 2000 + year }, rest)
+        } else {
+            let (year, rest) = text.split_at_checked(4)?;
+
+            (year.parse::<i64>().ok()?, rest)
+        };
+
+        let fields = (0..5)
+            .map(|index| rest.get(index * 2..index * 2 + 2)?.parse::<i64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        let [month, day, hour, minute, second] = fields[..] else {
+            return None;
+        };
+
+        let epoch = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+
+        Some(Time {
+            text: format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02} UTC"),
+            epoch: u64::try_from(epoch).ok()?,
+        })
+    }
+}
+
+/// What a server certificate says about who it belongs to, and for how long
+#[derive(Debug, PartialEq)]
+pub struct Certificate {
+    pub subject: String,
+    pub issuer: String,
+    /// Subject alternative names, the hostnames and addresses the certificate is valid for
+    pub names: Vec<String>,
+    pub not_before: Time,
+    pub not_after: Time,
+}
+
+/// `CN=example.org, O=Example`, in the order the attributes appear
+fn name(contents: &[u8]) -> String {
+    let mut parts = vec![];
+
+    let mut sets = Der::new(contents);
+
+    while let Some((_, set)) = sets.next() {
+        let mut attributes = Der::new(set);
+
+        while let Some(attribute) = attributes.expect(TAG_SEQUENCE) {
+            let mut attribute = Der::new(attribute);
+
+            let (Some(oid), Some((_, value))) = (attribute.expect(TAG_OID), attribute.next())
+            else {
+                continue;
+            };
+
+            let label = match oid {
+                [0x55, 0x04, kind] => ATTRIBUTES
+                    .iter()
+                    .find(|(known, _)| known == kind)
+                    .map(|(_, label)| label.to_string()),
+                _ => None,
+            };
+
+            let label = label.unwrap_or_else(|| {
+                oid.iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect::<String>()
+            });
+
+            parts.push(format!("{label}={}", String::from_utf8_lossy(value)));
+        }
+    }
+
+    parts.join(", ")
+}
+
+/// Hostnames and addresses from a `subjectAltName` extension value
+fn alt_names(contents: &[u8]) -> Vec<String> {
+    let mut names = vec![];
+
+    let mut general_names = Der::new(Der::new(contents).expect(TAG_SEQUENCE).unwrap_or_default());
+
+    while let Some((tag, value)) = general_names.next() {
+        match (tag, value.len()) {
+            (TAG_DNS_NAME, _) => names.push(String::from_utf8_lossy(value).into_owned()),
+            (TAG_IP_ADDRESS, 4) => {
+                let octets: [u8; 4] = value.try_into().unwrap_or_default();
+
+                names.push(std::net::Ipv4Addr::from(octets).to_string());
+            }
+            (TAG_IP_ADDRESS, 16) => {
+                let octets: [u8; 16] = value.try_into().unwrap_or_default();
+
+                names.push(std::net::Ipv6Addr::from(octets).to_string());
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+impl Certificate {
+    /// Reads the fields worth showing from an X.509 certificate in DER
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let certificate = Der::new(der).expect(TAG_SEQUENCE)?;
+
+        let mut tbs = Der::new(Der::new(certificate).expect(TAG_SEQUENCE)?);
+
+        let (tag, _) = tbs.next()?;
+
+        // The version is left out of v1 certificates, which start at the serial number
+        if tag == TAG_VERSION {
+            tbs.next()?;
+        }
+
+        // The signature algorithm, repeated outside the signed part
+        tbs.expect(TAG_SEQUENCE)?;
+
+        let issuer = name(tbs.expect(TAG_SEQUENCE)?);
+
+        let mut validity = Der::new(tbs.expect(TAG_SEQUENCE)?);
+
+        let (tag, contents) = validity.next()?;
+        let not_before = Time::parse(tag, contents)?;
+
+        let (tag, contents) = validity.next()?;
+        let not_after = Time::parse(tag, contents)?;
+
+        let subject = name(tbs.expect(TAG_SEQUENCE)?);
+
+        let mut names = vec![];
+
+        while let Some((tag, contents)) = tbs.next() {
+            if tag != TAG_EXTENSIONS {
+                continue;
+            }
+
+            let mut extensions = Der::new(Der::new(contents).expect(TAG_SEQUENCE)?);
+
+            while let Some(extension) = extensions.expect(TAG_SEQUENCE) {
+                let mut extension = Der::new(extension);
+
+                if extension.expect(TAG_OID) != Some(&OID_SUBJECT_ALT_NAME[..]) {
+                    continue;
+                }
+
+                // Skip the optional critical flag to reach the OCTET STRING
+                while let Some((tag, value)) = extension.next() {
+                    if tag == TAG_OCTET_STRING {
+                        names = alt_names(value);
+                    }
+                }
+            }
+        }
+
+        Some(Certificate {
+            subject,
+            issuer,
+            names,
+            not_before,
+            not_after,
+        })
+    }
+
+    pub fn report(&self) -> String {
+        let now = store::now();
+
+        let expiry = if self.not_after.epoch < now {
+            tr_with(
+                "cert-expired",
+                &[("days", &((now - self.not_after.epoch) / 86_400).to_string())],
+            )
+        } else {
+            tr_with(
+                "cert-expires-in",
+                &[("days", &((self.not_after.epoch - now) / 86_400).to_string())],
+            )
+        };
+
+        [
+            tr_with("cert-subject", &[("subject", &self.subject)]),
+            tr_with("cert-issuer", &[("issuer", &self.issuer)]),
+            tr_with("cert-names", &[("names", &self.names.join(", "))]),
+            tr_with(
+                "cert-validity",
+                &[
+                    ("from", &self.not_before.text),
+                    ("until", &self.not_after.text),
+                ],
+            ),
+            expiry,
+        ]
+        .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALHOST_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBlTCCATugAwIBAgIUTMCH5kl0ilIAQR4L3dsEzfe3N+kwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJbG9jYWxob3N0MCAXDTI2MTAxNjAxMTg0MloYDzIxMjYwOTIy
+MDExODQyWjAUMRIwEAYDVQQDDAlsb2NhbGhvc3QwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAAQE/IC+0Au5WJ7wEQNmFeq61SF+fUoip5EkOHnNrCK+6zntorpj6Qmv
+57QGoq4rjQrjEKXyq1wSNWJDccddhVZfo2kwZzAdBgNVHQ4EFgQU07/V+UoLmynJ
+Ii7WbARcR/G/DHkwHwYDVR0jBBgwFoAU07/V+UoLmynJIi7WbARcR/G/DHkwDwYD
+VR0TAQH/BAUwAwEB/zAUBgNVHREEDTALgglsb2NhbGhvc3QwCgYIKoZIzj0EAwID
+SAAwRQIgN3JpM6MlGZP4BtEoQD3Wrqtwyv5S9f9jrH9ClUHVkRoCIQDoZMEMvRIl
+K777UPtXB5zFjX1plLfwtR7ReiYAgVT3eg==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn read_certificates() {
+        let der = native_tls::Certificate::from_pem(LOCALHOST_CERT.as_bytes())
+            .and_then(|certificate| certificate.to_der())
+            .unwrap();
+
+        let certificate = Certificate::parse(&der).unwrap();
+
+        assert_eq!(certificate.subject, "CN=localhost");
+        assert_eq!(certificate.issuer, "CN=localhost");
+        assert_eq!(certificate.names, ["localhost"]);
+        assert_eq!(certificate.not_before.text, "2026-10-16 01:18:42 UTC");
+        assert_eq!(certificate.not_after.text, "2126-09-22 01:18:42 UTC");
+        assert_eq!(certificate.not_before.epoch, 1_792_113_522);
+
+        assert!(Certificate::parse(&der[..40]).is_none());
+    }
+}
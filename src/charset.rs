@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+/// Characters of windows-1252 at `0x80..0xa0`, where ISO-8859-1 has control codes
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Text encodings documents can be read in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Charset {
+    Utf8,
+    /// Also read for `iso-8859-1` and `us-ascii` labels, as browsers do
+    Windows1252,
+    /// Latin-1 with `€`, `Š`, `Œ` and a few more in place of rarely used signs
+    Iso885915,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Charset {
+    /// The encoding a `charset=` label names, ignoring case, e.g. `Windows-1252` or `latin1`
+    pub fn from_label(label: &str) -> Option<Self> {
+        let charset = match label.trim().trim_matches('"').to_lowercase().as_str() {
+            "utf-8" | "utf8" | "unicode-1-1-utf-8" => Charset::Utf8,
+            "windows-1252" | "cp1252" | "x-cp1252" | "iso-8859-1" | "iso8859-1" | "latin1"
+            | "l1" | "us-ascii" | "ascii" => Charset::Windows1252,
+            "iso-8859-15" | "iso8859-15" | "latin9" | "l9" => Charset::Iso885915,
+            "utf-16le" | "utf-16" => Charset::Utf16Le,
+            "utf-16be" => Charset::Utf16Be,
+            _ => return None,
+        };
+
+        Some(charset)
+    }
+
+    /// The `charset` parameter of a `Content-Type`, when it names a known encoding
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        content_type
+            .split(';')
+            .skip(1)
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+            .and_then(|(_, label)| Self::from_label(label))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "utf-8",
+            Charset::Windows1252 => "windows-1252",
+            Charset::Iso885915 => "iso-8859-15",
+            Charset::Utf16Le => "utf-16le",
+            Charset::Utf16Be => "utf-16be",
+        }
+    }
+
+    /// `bytes` as text, with anything that does not decode replaced
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        match self {
+            Charset::Utf8 => String::from_utf8_lossy(bytes),
+            Charset::Windows1252 => bytes.iter().map(|&byte| windows_1252(byte)).collect(),
+            Charset::Iso885915 => bytes.iter().map(|&byte| iso_8859_15(byte)).collect(),
+            Charset::Utf16Le => utf16(bytes, u16::from_le_bytes),
+            Charset::Utf16Be => utf16(bytes, u16::from_be_bytes),
+        }
+    }
+}
+
+fn windows_1252(byte: u8) -> char {
+    match byte {
+        0x80..0xa0 => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+fn iso_8859_15(byte: u8) -> char {
+    match byte {
+        0xa4 => '€',
+        0xa6 => 'Š',
+        0xa8 => 'š',
+        0xb4 => 'Ž',
+        0xb8 => 'ž',
+        0xbc => 'Œ',
+        0xbd => 'œ',
+        0xbe => 'Ÿ',
+        _ => char::from(byte),
+    }
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Cow<'_, str> {
+    let units = bytes
+        .chunks(2)
+        .map(|pair| match pair {
+            [first, second] => unit([*first, *second]),
+            // An odd byte out cannot be a character
+            _ => 0xfffd,
+        })
+        // A byte order mark is not part of the text
+        .skip_while(|&unit| unit == 0xfeff);
+
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_labels() {
+        assert_eq!(
+            Charset::from_content_type("text/html; Charset=\"ISO-8859-1\""),
+            Some(Charset::Windows1252)
+        );
+        assert_eq!(
+            Charset::from_content_type("text/plain;format=flowed;charset=utf-8"),
+            Some(Charset::Utf8)
+        );
+        assert_eq!(Charset::from_content_type("text/html"), None);
+        assert_eq!(Charset::from_label("shift_jis"), None);
+    }
+
+    #[test]
+    fn decode_legacy_encodings() {
+        assert_eq!(Charset::Windows1252.decode(b"caf\xe9 \x80\x96"), "café €–");
+        assert_eq!(Charset::Iso885915.decode(b"\xa4 \xbd\xe9"), "€ œé");
+        assert_eq!(Charset::Utf8.decode("Grüße".as_bytes()), "Grüße");
+        assert_eq!(
+            Charset::Utf16Le.decode(b"\xff\xfeH\0i\0=\xd8\x00\xde"),
+            "Hi😀"
+        );
+        assert_eq!(Charset::Utf16Be.decode(b"\0H\0i\0"), "Hi\u{fffd}");
+    }
+}
@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::store::{self, now};
+use crate::{Response, Scheme, Url};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+#[derive(Debug)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Without a `Domain` attribute the cookie goes back to the exact host only
+    pub host_only: bool,
+    pub path: String,
+    /// Seconds since the Unix epoch, or `None` for a session cookie
+    pub expires: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+/// Marks cookies hidden from scripts in the Netscape format, which has no column for it
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// Cookies set by servers, keyed by domain and path
+#[derive(Default)]
+pub struct CookieJar {
+    cookies: HashMap<(String, String), Vec<Cookie>>,
+}
+
+/// Suffixes under which unrelated sites register, beyond the top level domains
+const PUBLIC_SUFFIXES: [&str; 12] = [
+    "co.uk",
+    "org.uk",
+    "ac.uk",
+    "gov.uk",
+    "com.au",
+    "net.au",
+    "org.au",
+    "co.jp",
+    "co.nz",
+    "com.br",
+    "com.cn",
+    "github.io",
+];
+
+/// Whether a cookie for `domain` would reach sites run by different people, e.g. `com`
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || PUBLIC_SUFFIXES.contains(&domain)
+}
+
+fn domain_matches(hostname: &str, domain: &str) -> bool {
+    hostname == domain || hostname.ends_with(&format!(".{domain}"))
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/'))
+}
+
+/// The directory of the request path, used when `Set-Cookie` has no `Path`
+fn default_path(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => path[..end].to_string(),
+    }
+}
+
+// Days between 1970-01-01 and the given date, in the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parses cookie dates such as `Wed, 21 Oct 2015 07:28:00 GMT` or `Wed, 21-Oct-15 07:28:00 GMT`
+pub fn parse_date(date: &str) -> Option<u64> {
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+
+    for token in date.split(|ch: char| !ch.is_ascii_alphanumeric() && ch != ':') {
+        let lower = token.to_lowercase();
+
+        if time.is_none() && token.contains(':') {
+            let parts = token
+                .split(':')
+                .map(|part| part.parse::<i64>().ok())
+                .collect::<Option<Vec<_>>>()?;
+
+            let [hours, minutes, seconds] = parts[..] else {
+                return None;
+            };
+
+            time = Some(hours * 3600 + minutes * 60 + seconds);
+        } else if month.is_none() && lower.len() >= 3 && MONTHS.contains(&&lower[..3]) {
+            month = MONTHS.iter().position(|name| *name == &lower[..3]);
+        } else if let Ok(number) = token.parse::<i64>() {
+            if day.is_none() && token.len() <= 2 {
+                day = Some(number);
+            } else if year.is_none() {
+                year = Some(number);
+            }
+        }
+    }
+
+    let year = match year? {
+        year @ 0..=69 => year + 2000,
+        year @ 70..=99 => year + 1900,
+        year => year,
+    };
+
+    let days = days_from_civil(year, month? as i64 + 1, day?);
+
+    u64::try_from(days * 86_400 + time?).ok()
+}
+
+impl Cookie {
+    /// Reads one `Set-Cookie` value, received for `url`
+    pub fn parse(set_cookie: &str, url: &Url) -> Option<Self> {
+        let mut attributes = set_cookie.split(';');
+
+        let (name, value) = attributes.next()?.split_once('=')?;
+
+        let name = name.trim();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let hostname = url.hostname.to_lowercase();
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: hostname.clone(),
+            host_only: true,
+            path: default_path(&url.path),
+            expires: None,
+            secure: false,
+            http_only: false,
+        };
+
+        let mut max_age = None;
+
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+
+                    // Servers may only set cookies for themselves or a parent domain
+                    if !domain_matches(&hostname, &domain) {
+                        return None;
+                    }
+
+                    // A public suffix only names the host itself, as for `localhost`
+                    if is_public_suffix(&domain) {
+                        if domain != hostname {
+                            return None;
+                        }
+
+                        continue;
+                    }
+
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "expires" => cookie.expires = parse_date(value).or(cookie.expires),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+
+        // Over plain HTTP a server cannot vouch for cookies meant for secure connections only
+        if cookie.secure && url.scheme != Scheme::Https {
+            return None;
+        }
+
+        // Max-Age wins over Expires, and zero or less expires the cookie right away
+        if let Some(max_age) = max_age {
+            cookie.expires = Some(match u64::try_from(max_age) {
+                Ok(seconds) if seconds > 0 => now().saturating_add(seconds),
+                _ => 0,
+            });
+        }
+
+        Some(cookie)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let hostname = url.hostname.to_lowercase();
+
+        let domain = if self.host_only {
+            hostname == self.domain
+        } else {
+            domain_matches(&hostname, &self.domain)
+        };
+
+        domain
+            && path_matches(&url.path, &self.path)
+            && (!self.secure || url.scheme == Scheme::Https)
+    }
+}
+
+impl CookieJar {
+    /// `$XDG_DATA_HOME/voy/cookies.txt`, or `~/.local/share/voy/cookies.txt` when that is not set
+    pub fn default_path() -> Option<PathBuf> {
+        Some(store::data_dir()?.join("cookies.txt"))
+    }
+
+    /// Reads a jar saved in the Netscape `cookies.txt` format, as curl and wget also use
+    pub fn load(path: &Path) -> Self {
+        let mut jar = CookieJar::default();
+
+        let Ok(source) = fs::read_to_string(path) else {
+            return jar;
+        };
+
+        for line in source.lines() {
+            let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+                Some(line) => (line, true),
+                None if line.starts_with('#') => continue,
+                None => (line, false),
+            };
+
+            let fields = line.split('\t').collect::<Vec<_>>();
+
+            let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+                continue;
+            };
+
+            let Ok(expires) = expires.parse::<u64>() else {
+                continue;
+            };
+
+            jar.insert(Cookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                domain: domain.trim_start_matches('.').to_string(),
+                host_only: subdomains != "TRUE",
+                path: path.to_string(),
+                expires: Some(expires),
+                secure: secure == "TRUE",
+                http_only,
+            });
+        }
+
+        jar
+    }
+
+    /// Writes the cookies that outlive the session; session cookies are left behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let now = now();
+
+        let mut source = String::from("# Netscape HTTP Cookie File\n");
+
+        for cookie in self.cookies.values().flatten() {
+            let Some(expires) = cookie.expires.filter(|_| !cookie.is_expired(now)) else {
+                continue;
+            };
+
+            let flag = |set: bool| if set { "TRUE" } else { "FALSE" };
+
+            source.push_str(&format!(
+                "{prefix}{domain}\t{subdomains}\t{path}\t{secure}\t{expires}\t{name}\t{value}\n",
+                prefix = if cookie.http_only {
+                    HTTP_ONLY_PREFIX
+                } else {
+                    ""
+                },
+                domain = if cookie.host_only {
+                    cookie.domain.clone()
+                } else {
+                    format!(".{}", cookie.domain)
+                },
+                subdomains = flag(!cookie.host_only),
+                path = cookie.path,
+                secure = flag(cookie.secure),
+                name = cookie.name,
+                value = cookie.value,
+            ));
+        }
+
+        // Session tokens are as good as passwords
+        store::write_private(path, source.as_bytes())
+    }
+
+    /// Keeps the cookies from every `Set-Cookie` header of the response
+    pub fn store(&mut self, url: &Url, response: &Response) {
+        let Some(set_cookie) = response.headers.get("set-cookie") else {
+            return;
+        };
+
+        for cookie in set_cookie
+            .lines()
+            .filter_map(|line| Cookie::parse(line, url))
+        {
+            self.insert(cookie);
+        }
+    }
+
+    pub fn insert(&mut self, cookie: Cookie) {
+        let now = now();
+
+        let key = (cookie.domain.clone(), cookie.path.clone());
+
+        let cookies = self.cookies.entry(key.clone()).or_default();
+
+        cookies.retain(|existing| existing.name != cookie.name);
+
+        // Setting an expired cookie is how servers delete one
+        if !cookie.is_expired(now) {
+            cookies.push(cookie);
+        }
+
+        if cookies.is_empty() {
+            self.cookies.remove(&key);
+        }
+    }
+
+    /// Every live cookie, by domain, then path, then name
+    pub fn cookies(&self) -> Vec<&Cookie> {
+        let now = now();
+
+        let mut cookies = self
+            .cookies
+            .values()
+            .flatten()
+            .filter(|cookie| !cookie.is_expired(now))
+            .collect::<Vec<_>>();
+
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+
+        cookies
+    }
+
+    /// Drops one cookie, returning whether it was stored
+    pub fn remove(&mut self, domain: &str, path: &str, name: &str) -> bool {
+        let key = (domain.to_string(), path.to_string());
+
+        let Some(cookies) = self.cookies.get_mut(&key) else {
+            return false;
+        };
+
+        let before = cookies.len();
+
+        cookies.retain(|cookie| cookie.name != name);
+
+        let removed = cookies.len() < before;
+
+        if cookies.is_empty() {
+            self.cookies.remove(&key);
+        }
+
+        removed
+    }
+
+    /// Drops the cookies of `site` and its subdomains, returning how many there were
+    pub fn remove_site(&mut self, site: &str) -> usize {
+        let site = site.to_lowercase();
+
+        let before = self.cookies.values().map(Vec::len).sum::<usize>();
+
+        self.cookies
+            .retain(|(domain, _), _| !domain_matches(domain, &site));
+
+        before - self.cookies.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// The `Cookie` header value for a request to `url`, longest paths first, then by name
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let now = now();
+
+        let mut cookies = self
+            .cookies
+            .values()
+            .flatten()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(url))
+            .collect::<Vec<_>>();
+
+        if cookies.is_empty() {
+            return None;
+        }
+
+        cookies.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let pairs = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>();
+
+        Some(pairs.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn parse_cookie_dates() {
+        assert_eq!(
+            parse_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(1445412480)
+        );
+        assert_eq!(
+            parse_date("Wednesday, 21-Oct-15 07:28:00 GMT"),
+            Some(1445412480)
+        );
+        assert_eq!(parse_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(parse_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn persist_cookies() {
+        let path = env::temp_dir().join(format!("voy-cookies-{}.txt", std::process::id()));
+
+        let url = Url::new("https://www.example.org/").unwrap();
+
+        let mut jar = CookieJar::default();
+
+        for set_cookie in [
+            "session=abc",
+            "login=1; Max-Age=3600; Secure; HttpOnly",
+            "theme=dark; Domain=example.org; Path=/; Expires=Wed, 21 Oct 2099 07:28:00 GMT",
+        ] {
+            jar.insert(Cookie::parse(set_cookie, &url).unwrap());
+        }
+
+        jar.save(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let jar = CookieJar::load(&path);
+
+        assert_eq!(jar.header(&url).as_deref(), Some("login=1; theme=dark"));
+        assert_eq!(
+            jar.header(&Url::new("http://cdn.example.org/").unwrap())
+                .as_deref(),
+            Some("theme=dark")
+        );
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            CookieJar::load(&env::temp_dir().join("voy-missing.txt")).header(&url),
+            None
+        );
+    }
+
+    #[test]
+    fn send_matching_cookies() {
+        let mut jar = CookieJar::default();
+
+        let url = Url::new("https://www.example.org/account/login").unwrap();
+
+        for set_cookie in [
+            "session=abc; Path=/; Secure; HttpOnly",
+            "theme=dark; Domain=.example.org; Path=/",
+            "login=1",
+            "tracker=x; Domain=ads.example",
+            "old=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+        ] {
+            if let Some(cookie) = Cookie::parse(set_cookie, &url) {
+                jar.insert(cookie);
+            }
+        }
+
+        assert_eq!(
+            jar.header(&Url::new("https://www.example.org/account/settings").unwrap())
+                .as_deref(),
+            Some("login=1; session=abc; theme=dark")
+        );
+
+        assert_eq!(
+            jar.header(&Url::new("http://www.example.org/account/").unwrap())
+                .as_deref(),
+            Some("login=1; theme=dark")
+        );
+
+        assert_eq!(
+            jar.header(&Url::new("http://cdn.example.org/").unwrap())
+                .as_deref(),
+            Some("theme=dark")
+        );
+
+        assert_eq!(jar.header(&Url::new("https://ads.example/").unwrap()), None);
+
+        jar.insert(
+            Cookie::parse("theme=light; Domain=example.org; Path=/; Max-Age=60", &url).unwrap(),
+        );
+
+        assert_eq!(
+            jar.header(&Url::new("http://example.org/").unwrap())
+                .as_deref(),
+            Some("theme=light")
+        );
+
+        jar.insert(Cookie::parse("theme=; Domain=example.org; Path=/; Max-Age=0", &url).unwrap());
+
+        assert_eq!(jar.header(&Url::new("http://example.org/").unwrap()), None);
+    }
+
+    #[test]
+    fn refuse_cookies_beyond_the_site() {
+        let url = Url::new("https://shop.example.co.uk/").unwrap();
+
+        assert!(Cookie::parse("id=1; Domain=uk", &url).is_none());
+        assert!(Cookie::parse("id=1; Domain=.co.uk", &url).is_none());
+        assert!(Cookie::parse("id=1; Domain=example.co.uk", &url).is_some_and(|c| !c.host_only));
+
+        let cookie = Cookie::parse(
+            "id=1; Domain=localhost",
+            &Url::new("http://localhost/").unwrap(),
+        );
+
+        assert!(cookie.is_some_and(|c| c.host_only));
+
+        assert!(Cookie::parse("id=1; Secure", &Url::new("http://example.org/").unwrap()).is_none());
+        assert!(Cookie::parse("id=1; Secure", &url).is_some_and(|c| c.secure));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::base64;
+use crate::i18n::tr;
+use crate::percent_decode_bytes;
+
+/// What a data URL without a media type holds (RFC 2397)
+const DEFAULT_MEDIA_TYPE: &str = "text/plain;charset=US-ASCII";
+
+/// The content of a `data:` URL
+#[derive(Debug, PartialEq)]
+pub struct Data {
+    /// E.g. `text/html;charset=utf-8`, as a `Content-Type` would carry it
+    pub media_type: String,
+    pub body: Vec<u8>,
+}
+
+/// Reads a data URL without its `data:`, e.g. `text/html;base64,PGgxPkhpPC9oMT4=`
+///
+/// The data is percent-decoded, then base64-decoded when `;base64` closes the media type.
+pub fn parse(url: &str) -> Result<Data, String> {
+    let Some((header, data)) = url.split_once(',') else {
+        return Err(tr("data-no-comma"));
+    };
+
+    let (media_type, encoded) = match header.rsplit_once(';') {
+        Some((media_type, flag)) if flag.trim().eq_ignore_ascii_case("base64") => {
+            (media_type.trim(), true)
+        }
+        _ => (header.trim(), false),
+    };
+
+    // Parameters alone, e.g. `;charset=utf-8`, still describe plain text
+    let media_type = match media_type {
+        "" => DEFAULT_MEDIA_TYPE.to_string(),
+        parameters if parameters.starts_with(';') => format!("text/plain{parameters}"),
+        media_type => media_type.to_string(),
+    };
+
+    let bytes = percent_decode_bytes(data);
+
+    let body = match encoded {
+        true => {
+            base64::decode(&String::from_utf8_lossy(&bytes)).ok_or_else(|| tr("data-bad-base64"))?
+        }
+        false => bytes,
+    };
+
+    Ok(Data { media_type, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_data_urls() {
+        let data = |media_type: &str, body: &[u8]| Data {
+            media_type: media_type.to_string(),
+            body: body.to_vec(),
+        };
+
+        assert_eq!(
+            parse("text/html,<p>a, b</p>").unwrap(),
+            data("text/html", b"<p>a, b</p>")
+        );
+        assert_eq!(
+            parse(",A%20brief%20note").unwrap(),
+            data("text/plain;charset=US-ASCII", b"A brief note")
+        );
+        assert_eq!(
+            parse(";charset=utf-8;base64,w6lt").unwrap(),
+            data("text/plain;charset=utf-8", "ém".as_bytes())
+        );
+        assert_eq!(
+            parse("image/gif;BASE64,R0lG%0AODlh").unwrap(),
+            data("image/gif", b"GIF89a")
+        );
+        assert_eq!(
+            parse("application/octet-stream,%00%FF").unwrap(),
+            data("application/octet-stream", &[0, 0xff])
+        );
+
+        assert!(parse("text/html").is_err());
+        assert!(parse("text/plain;base64,not base64!").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::encoding;
+use crate::i18n::tr_with;
+use crate::window;
+
+const BAR_WIDTH: usize = 30;
+
+/// How often the progress bar is redrawn
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The file a successful response is written to with `-o` or `-O`, as it arrives
+#[derive(Debug)]
+pub struct Download {
+    path: PathBuf,
+    /// Draws a progress bar on stderr, when that is a terminal
+    progress: bool,
+    saved: AtomicBool,
+}
+
+/// The last segment of a URL or path, e.g. `guide.html`, or `index.html` when there is none
+pub fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+
+    match path.rsplit('/').next() {
+        Some(name) if !name.is_empty() && !name.contains("://") && name != ".." => name,
+        _ => "index.html",
+    }
+}
+
+/// Where to save a copy of `url` in the working directory, named after its last segment
+/// without replacing any file
+pub fn save_path(url: &str) -> PathBuf {
+    let name = file_name(url);
+
+    let mut candidate = PathBuf::from(name);
+
+    let mut copy = 1;
+
+    while candidate.exists() {
+        candidate = PathBuf::from(format!("{copy}-{name}"));
+
+        copy += 1;
+    }
+
+    candidate
+}
+
+/// `512 B`, `1.5 KB`, `12.0 MB`, in powers of a thousand like the rates of `--throttle`
+pub fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// One line of progress, with a bar when the length is known
+fn line(name: &str, done: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let rate = match elapsed.as_secs_f64() {
+        seconds if seconds > 0.0 => size((done as f64 / seconds) as u64),
+        _ => size(0),
+    };
+
+    let Some(total) = total.filter(|total| *total > 0) else {
+        return format!(
+            "{name} {}",
+            tr_with(
+                "download-progress",
+                &[("done", &size(done)), ("rate", &rate)]
+            )
+        );
+    };
+
+    let ratio = (done as f64 / total as f64).min(1.0);
+
+    let filled = (ratio * BAR_WIDTH as f64) as usize;
+
+    format!(
+        "{name} [{}{}] {:>3}% {}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        (ratio * 100.0) as u64,
+        tr_with(
+            "download-progress-of",
+            &[
+                ("done", &size(done)),
+                ("total", &size(total)),
+                ("rate", &rate)
+            ]
+        )
+    )
+}
+
+/// Counts the bytes read off the wire, redrawing the progress bar as they come
+struct Progress<'a, R> {
+    inner: R,
+    name: &'a str,
+    total: Option<u64>,
+    done: u64,
+    started: Instant,
+    drawn: Option<Instant>,
+    show: bool,
+}
+
+impl<R> Progress<'_, R> {
+    fn draw(&mut self) {
+        if !self.show {
+            return;
+        }
+
+        self.drawn = Some(Instant::now());
+
+        eprint!(
+            "\r{}",
+            line(self.name, self.done, self.total, self.started.elapsed())
+        );
+
+        window::set_progress(
+            self.total
+                .filter(|total| *total > 0)
+                .map(|total| self.done * 100 / total),
+        );
+    }
+}
+
+impl<R: Read> Read for Progress<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+
+        self.done += read as u64;
+
+        if self
+            .drawn
+            .is_none_or(|drawn| drawn.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw();
+        }
+
+        Ok(read)
+    }
+}
+
+impl Download {
+    pub fn new(path: PathBuf) -> Self {
+        Download {
+            path,
+            progress: io::stderr().is_terminal(),
+            saved: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether a body has been written, so it is not written again
+    pub fn is_saved(&self) -> bool {
+        self.saved.load(Ordering::Relaxed)
+    }
+
+    /// Decodes `body` into the file as it is read, with `total` the bytes expected on the wire
+    ///
+    /// Whatever the decoder leaves unread is drained, so the connection stays usable.
+    pub fn save<R: Read>(
+        &self,
+        body: &mut R,
+        encoding: Option<&str>,
+        total: Option<u64>,
+    ) -> io::Result<u64> {
+        let name = self.path.display().to_string();
+
+        let mut file = File::create(&self.path)
+            .map(BufWriter::new)
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    tr_with(
+                        "download-failed",
+                        &[("path", &name), ("error", &err.to_string())],
+                    ),
+                )
+            })?;
+
+        let mut progress = Progress {
+            inner: &mut *body,
+            name: &name,
+            total,
+            done: 0,
+            started: Instant::now(),
+            drawn: None,
+            show: self.progress,
+        };
+
+        let written = io::copy(&mut encoding::decoder(encoding, &mut progress)?, &mut file)?;
+
+        file.flush()?;
+
+        progress.draw();
+
+        if progress.show {
+            eprintln!();
+
+            window::clear_progress();
+        }
+
+        io::copy(body, &mut io::sink())?;
+
+        self.saved.store(true, Ordering::Relaxed);
+
+        Ok(written)
+    }
+
+    /// Writes a body that was already read whole, e.g. from a `file:` URL
+    pub fn write(&self, body: &[u8]) -> io::Result<()> {
+        fs::write(&self.path, body)?;
+
+        self.saved.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::env;
+    use std::io::Cursor;
+
+    #[test]
+    fn draw_progress() {
+        assert_eq!(size(999), "999 B");
+        assert_eq!(size(1_500), "1.5 KB");
+        assert_eq!(size(12_000_000), "12.0 MB");
+
+        assert_eq!(
+            line("a.zip", 500, Some(2000), Duration::from_secs(2)),
+            "a.zip [#######-----------------------]  25% 500 B of 2.0 KB, 250 B/s"
+        );
+        assert_eq!(
+            line("a.zip", 3000, None, Duration::from_secs(1)),
+            "a.zip 3.0 KB, 3.0 KB/s"
+        );
+
+        assert_eq!(file_name("https://example.org/files/a.zip?v=1"), "a.zip");
+        assert_eq!(file_name("/"), "index.html");
+    }
+
+    #[test]
+    fn save_decoded_bodies() {
+        let path = env::temp_dir().join(format!("voy-download-{}.txt", std::process::id()));
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+
+        encoder.write_all(b"Hello, file").unwrap();
+
+        let mut gzipped = encoder.finish().unwrap();
+
+        // Bytes after the gzip member are drained rather than left on the connection
+        gzipped.extend(b"??");
+
+        let mut body = Cursor::new(gzipped);
+
+        let download = Download::new(path.clone());
+
+        let written = download.save(&mut body, Some("gzip"), None).unwrap();
+
+        let saved = fs::read(&path).unwrap();
+
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(written, 11);
+        assert_eq!(saved, b"Hello, file");
+        assert_eq!(body.position(), body.get_ref().len() as u64);
+        assert!(download.is_saved());
+    }
+
+    #[test]
+    fn name_saved_copies() {
+        assert_eq!(
+            save_path("https://example.org/docs/guide.html?v=2"),
+            PathBuf::from("guide.html")
+        );
+        assert_eq!(
+            save_path("https://example.org/"),
+            PathBuf::from("index.html")
+        );
+    }
+}
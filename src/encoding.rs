@@ -0,0 +1,209 @@
+use brotli_decompressor::Decompressor;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::i18n::tr_with;
+
+/// Encodings we can undo, in the form advertised through `Accept-Encoding`
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Undoes a `Content-Encoding`, which may list several codings applied in order, keeping at
+/// most `limit` bytes and reporting whether there were more
+///
+/// A few kilobytes of gzip can expand to gigabytes, and stacked codings such as `gzip, gzip`
+/// multiply that, so each stage is held to the cap however small the body.
+pub fn decode_body(
+    encoding: Option<&str>,
+    mut body: Vec<u8>,
+    limit: u64,
+) -> io::Result<(Vec<u8>, bool)> {
+    let mut truncated = false;
+
+    for coding in codings(encoding) {
+        let mut decoded = vec![];
+
+        match stage(&coding, &body[..])?
+            .take(limit + 1)
+            .read_to_end(&mut decoded)
+        {
+            Ok(_) => {}
+            // What an earlier stage cut short ends early here too, keeping what decoded
+            Err(err) if truncated && err.kind() == io::ErrorKind::UnexpectedEof => {}
+            Err(err) => return Err(err),
+        }
+
+        truncated |= decoded.len() as u64 > limit;
+
+        decoded.truncate(limit as usize);
+
+        body = decoded;
+    }
+
+    Ok((body, truncated))
+}
+
+/// Undoes a `Content-Encoding` as the body is read, for bodies too large to hold in memory
+pub fn decoder<'a>(encoding: Option<&str>, body: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let mut reader: Box<dyn Read + 'a> = Box::new(body);
+
+    for coding in codings(encoding) {
+        reader = stage(&coding, reader)?;
+    }
+
+    Ok(reader)
+}
+
+/// The codings of a `Content-Encoding` in the order they are undone, the last applied first
+fn codings(encoding: Option<&str>) -> impl Iterator<Item = String> + '_ {
+    encoding
+        .unwrap_or_default()
+        .rsplit(',')
+        .map(|coding| coding.trim().to_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+}
+
+/// Undoes one coding, e.g. `gzip`
+fn stage<'a>(coding: &str, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let reader: Box<dyn Read + 'a> = match coding {
+        "gzip" | "x-gzip" => Box::new(GzDecoder::new(reader)),
+        "deflate" => {
+            let mut buffered = BufReader::new(reader);
+
+            if is_zlib(buffered.fill_buf()?) {
+                Box::new(ZlibDecoder::new(buffered))
+            } else {
+                Box::new(DeflateDecoder::new(buffered))
+            }
+        }
+        "br" => Box::new(Decompressor::new(reader, BROTLI_BUFFER_SIZE)),
+        _ => {
+            return Err(io::Error::other(tr_with(
+                "unsupported-encoding",
+                &[("encoding", coding)],
+            )))
+        }
+    };
+
+    Ok(reader)
+}
+
+// `deflate` is meant to be zlib wrapped, but plenty of servers send raw deflate
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    const LIMIT: u64 = 1024;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+
+        encoder.write_all(data).unwrap();
+
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decode_gzip() {
+        let body = gzip(b"<p>Hello</p>");
+
+        assert_eq!(
+            decode_body(Some("gzip"), body, LIMIT).unwrap().0,
+            b"<p>Hello</p>"
+        );
+
+        let body = gzip(&gzip(b"twice"));
+
+        assert_eq!(
+            decode_body(Some("gzip, GZIP"), body, LIMIT).unwrap().0,
+            b"twice"
+        );
+
+        assert_eq!(
+            decode_body(None, b"plain".to_vec(), LIMIT).unwrap().0,
+            b"plain"
+        );
+
+        assert!(decode_body(Some("compress"), b"plain".to_vec(), LIMIT).is_err());
+    }
+
+    #[test]
+    fn cap_decompression_bombs() {
+        let body = gzip(&vec![0; 1024 * 1024]);
+
+        assert!(body.len() < 4096);
+
+        let (decoded, truncated) = decode_body(Some("gzip"), body, LIMIT).unwrap();
+
+        assert_eq!(decoded.len() as u64, LIMIT);
+        assert!(truncated);
+
+        // Each stage is capped, so the inner gzip is cut short rather than expanded in full
+        let body = gzip(&gzip(&vec![0; 8 * 1024 * 1024]));
+
+        let (decoded, truncated) = decode_body(Some("gzip, gzip"), body, LIMIT).unwrap();
+
+        assert!(decoded.len() as u64 <= LIMIT);
+        assert!(decoded.iter().all(|&byte| byte == 0));
+        assert!(truncated);
+    }
+
+    #[test]
+    fn decode_deflate() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+
+        encoder.write_all(b"zlib wrapped").unwrap();
+
+        let body = encoder.finish().unwrap();
+
+        assert_eq!(
+            decode_body(Some("deflate"), body, LIMIT).unwrap().0,
+            b"zlib wrapped"
+        );
+
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+
+        encoder.write_all(b"raw deflate").unwrap();
+
+        let body = encoder.finish().unwrap();
+
+        assert_eq!(
+            decode_body(Some("deflate"), body, LIMIT).unwrap().0,
+            b"raw deflate"
+        );
+    }
+
+    #[test]
+    fn decode_brotli() {
+        // A single uncompressed meta-block holding "hello", then the empty last one
+        let mut body = vec![0x40, 0x00, 0x10];
+
+        body.extend(b"hello");
+        body.push(0x03);
+
+        assert_eq!(
+            decode_body(Some("br"), body.clone(), LIMIT).unwrap().0,
+            b"hello"
+        );
+
+        let body = gzip(&body);
+
+        assert_eq!(
+            decode_body(Some("br, gzip"), body, LIMIT).unwrap().0,
+            b"hello"
+        );
+
+        assert!(decode_body(Some("br"), b"hello".to_vec(), LIMIT).is_err());
+    }
+}
@@ -70,6 +70,28 @@ impl EntityParser {
         None
     }
 
+    /// Replaces every character reference in `text`, e.g. within attribute values
+    pub fn decode(&self, text: &str) -> String {
+        let mut decoded = String::new();
+
+        let mut it = text.chars().peekable();
+
+        while let Some(&next) = it.peek() {
+            if next == '&' {
+                if let Some(entity) = self.consume(&mut it) {
+                    decoded.push_str(&entity);
+                }
+
+                continue;
+            }
+
+            decoded.push(next);
+            it.next();
+        }
+
+        decoded
+    }
+
     fn execute(&self, input: &str) -> Option<String> {
         if let Some((_, codepoints)) = self.0.iter().find(|(name, _)| name == input) {
             return Some(
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::io;
+
+use crate::about::escape;
+use crate::i18n::{tr, tr_with};
+use crate::suggest;
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Resolve(String),
+    Connect(String, io::Error),
+    Tls(String, String),
+    Timeout(String),
+    TooManyRedirects(String),
+    RedirectLoop(String),
+    Blocked(String),
+    /// The proxy would not open a tunnel, with the status line it answered
+    Proxy(String, String),
+    /// The FTP server refused a command, with the reply it gave
+    Ftp(String, String),
+    /// The address typed or redirected to could not be parsed, e.g. a port beyond 65535
+    InvalidUrl(String),
+}
+
+impl NetworkError {
+    pub fn from_io(host: &str, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                NetworkError::Timeout(host.to_owned())
+            }
+            _ => NetworkError::Connect(host.to_owned(), err),
+        }
+    }
+
+    /// Failures a later attempt may not run into, e.g. a reset connection
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NetworkError::Timeout(_) => true,
+            NetworkError::Connect(_, err) => matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            NetworkError::Resolve(_) => "resolve",
+            NetworkError::Connect(_, _) => "connect",
+            NetworkError::Tls(_, _) => "tls",
+            NetworkError::Timeout(_) => "timeout",
+            NetworkError::TooManyRedirects(_) => "redirects",
+            NetworkError::RedirectLoop(_) => "redirect-loop",
+            NetworkError::Blocked(_) => "blocked",
+            NetworkError::Proxy(_, _) => "proxy",
+            NetworkError::Ftp(_, _) => "ftp",
+            NetworkError::InvalidUrl(_) => "invalid-url",
+        }
+    }
+
+    pub fn title(&self) -> String {
+        tr(&format!("{}-title", self.key()))
+    }
+
+    pub fn hint(&self) -> String {
+        tr(&format!("{}-hint", self.key()))
+    }
+
+    /// Hostname the user most likely meant when it failed to resolve
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            NetworkError::Resolve(hostname) => suggest::hostname(hostname),
+            _ => None,
+        }
+    }
+
+    /// Internal page shown in place of the document that failed to load, with the hosts,
+    /// URLs and server replies it quotes escaped
+    pub fn page(&self) -> String {
+        let suggestion = self
+            .suggestion()
+            .map(|host| {
+                format!(
+                    "<p>{}</p>\n",
+                    escape(&tr_with("resolve-suggestion", &[("host", &host)]))
+                )
+            })
+            .unwrap_or_default();
+
+        format!(
+            "<html><body>\n<h1>{title}</h1>\n<p>{detail}</p>\n{suggestion}<p>{hint}</p>\n</body></html>",
+            title = self.title(),
+            detail = escape(&self.to_string()),
+            hint = self.hint(),
+        )
+    }
+}
+
+/// Internal page shown when loading or rendering a page panicked
+pub fn crash_page(reason: &str) -> String {
+    format!(
+        "<html><body>\n<h1>{title}</h1>\n<p>{detail}</p>\n<p>{reason}</p>\n</body></html>",
+        title = tr("crash-title"),
+        detail = tr("crash-detail"),
+        reason = escape(reason),
+    )
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let detail = match self {
+            NetworkError::Resolve(host) => tr_with("resolve-detail", &[("host", host)]),
+            NetworkError::Connect(host, err) => tr_with(
+                "connect-detail",
+                &[("host", host), ("reason", &err.to_string())],
+            ),
+            NetworkError::Tls(host, reason) => {
+                tr_with("tls-detail", &[("host", host), ("reason", reason)])
+            }
+            NetworkError::Timeout(host) => tr_with("timeout-detail", &[("host", host)]),
+            NetworkError::TooManyRedirects(url) => tr_with("redirects-detail", &[("url", url)]),
+            NetworkError::RedirectLoop(url) => tr_with("redirect-loop-detail", &[("url", url)]),
+            NetworkError::Blocked(url) => tr_with("blocked-detail", &[("url", url)]),
+            NetworkError::InvalidUrl(url) => tr_with("invalid-url-detail", &[("url", url)]),
+            NetworkError::Proxy(proxy, status) => {
+                tr_with("proxy-detail", &[("proxy", proxy), ("status", status)])
+            }
+            NetworkError::Ftp(host, reply) => {
+                tr_with("ftp-detail", &[("host", host), ("reply", reply)])
+            }
+        };
+
+        write!(f, "{detail}")
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_are_classified() {
+        let err = io::Error::from(io::ErrorKind::TimedOut);
+
+        assert!(matches!(
+            NetworkError::from_io("example.org:80", err),
+            NetworkError::Timeout(_)
+        ));
+
+        let err = io::Error::from(io::ErrorKind::ConnectionRefused);
+
+        assert!(matches!(
+            NetworkError::from_io("example.org:80", err),
+            NetworkError::Connect(_, _)
+        ));
+    }
+
+    #[test]
+    fn resolve_failures_suggest_a_fix() {
+        let err = NetworkError::Resolve("ww.example.com".to_string());
+
+        assert_eq!(err.suggestion().as_deref(), Some("www.example.com"));
+        assert!(err.page().contains("www.example.com?"));
+    }
+
+    #[test]
+    fn pages_escape_what_they_quote() {
+        let err = NetworkError::Blocked("http://example.org/<script>".to_string());
+
+        assert!(err.page().contains("http://example.org/&lt;script&gt;"));
+        assert!(crash_page("Unexpected <b>").contains("Unexpected &lt;b&gt;"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::i18n::{tr, tr_with};
+
+/// Writes the page source to a temporary file and blocks until `$EDITOR` exits
+pub fn open_in_editor(source: &str) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // `code -w` is a program and its arguments
+    let mut parts = editor.split_whitespace();
+
+    let program = parts.next().unwrap_or("vi");
+
+    let (path, mut file) = temp_file()?;
+
+    let written = file.write_all(source.as_bytes());
+
+    drop(file);
+
+    let status = written.and_then(|_| Command::new(program).args(parts).arg(&path).status());
+
+    let _ = fs::remove_file(&path);
+
+    let status = status?;
+
+    if !status.success() {
+        let status = status.to_string();
+
+        return Err(io::Error::other(tr_with(
+            "editor-failed",
+            &[("status", &status)],
+        )));
+    }
+
+    Ok(())
+}
+
+/// A new file in the temporary directory, under a name no other file had, so nothing placed
+/// there beforehand, such as a link, is written through
+fn temp_file() -> io::Result<(PathBuf, File)> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+
+    for attempt in 0u32.. {
+        let path = env::temp_dir().join(format!(
+            "browser-voy-{}-{nanos:x}-{attempt}.html",
+            std::process::id()
+        ));
+
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    unreachable!("ran out of temporary file names")
+}
+
+/// Hands the URL over to the system default browser
+pub fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        // Not through `cmd /C start`, where `&` or `|` in a link would start another command
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(url).spawn()?;
+
+    Ok(())
+}
+
+/// Launches a user configured handler, e.g. `irssi -c`, with the URL as last argument
+pub fn launch_handler(command: &str, url: &str) -> io::Result<()> {
+    let mut parts = command.split_whitespace();
+
+    let Some(program) = parts.next() else {
+        return Err(io::Error::other(tr("empty-handler")));
+    };
+
+    Command::new(program).args(parts).arg(url).spawn()?;
+
+    Ok(())
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+
+use crate::connection::{dial, Settings};
+use crate::error::NetworkError;
+use crate::listing::{self, Entry};
+use crate::multipart::sniff;
+use crate::resolver;
+use crate::{percent_decode, read_capped, Response, Url, MAX_DOCUMENT_BYTES};
+
+/// Logged in as when the URL names no user, by the convention of RFC 1635
+const ANONYMOUS_USER: &str = "anonymous";
+
+/// Anonymous servers ask for an email address as the password, any will do
+const ANONYMOUS_PASSWORD: &str = "voy@";
+
+/// The control connection of an FTP session (RFC 959), commands one at a time
+struct Session {
+    control: BufReader<TcpStream>,
+    host: String,
+}
+
+impl Session {
+    /// Connects and logs in, as `anonymous` without a user in the URL
+    fn open(url: &Url, settings: &Settings) -> Result<Self, NetworkError> {
+        let control = BufReader::new(dial(&url.host, &url.hostname, settings)?);
+
+        let mut session = Session {
+            control,
+            host: url.host.clone(),
+        };
+
+        session.expect(2)?;
+
+        let (user, password) = match &url.userinfo {
+            Some((user, password)) => (user.as_str(), password.as_str()),
+            None => (ANONYMOUS_USER, ANONYMOUS_PASSWORD),
+        };
+
+        session.send(&format!("USER {user}"))?;
+
+        // 230 lets a user in without a password
+        match session.reply()? {
+            (230, _) => {}
+            (331, _) => {
+                session.command(&format!("PASS {password}"))?;
+            }
+            (_, reply) => return Err(NetworkError::Ftp(session.host, reply)),
+        }
+
+        // Binary, so files arrive as they are stored
+        session.command("TYPE I")?;
+
+        Ok(session)
+    }
+
+    /// Sends `line` and reads the reply, which has to be a success
+    fn command(&mut self, line: &str) -> Result<(u16, String), NetworkError> {
+        self.send(line)?;
+
+        self.expect(2)
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), NetworkError> {
+        self.control
+            .get_mut()
+            .write_all(format!("{line}\r\n").as_bytes())
+            .map_err(|err| NetworkError::from_io(&self.host, err))
+    }
+
+    /// Reads a reply, failing unless its code starts with `class`, e.g. 2 for success
+    fn expect(&mut self, class: u16) -> Result<(u16, String), NetworkError> {
+        let (code, reply) = self.reply()?;
+
+        if code / 100 != class {
+            return Err(NetworkError::Ftp(self.host.clone(), reply));
+        }
+
+        Ok((code, reply))
+    }
+
+    /// The code and first line of a reply, skipping the rest of a multiline one
+    fn reply(&mut self) -> Result<(u16, String), NetworkError> {
+        self.read_reply()
+            .map_err(|err| NetworkError::from_io(&self.host, err))
+    }
+
+    fn read_reply(&mut self) -> io::Result<(u16, String)> {
+        let first = self.line()?;
+
+        let code = first
+            .get(..3)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, first.clone()))?;
+
+        // `123-First line` goes on until a line starting `123 `
+        if first.as_bytes().get(3) == Some(&b'-') {
+            let last = format!("{code} ");
+
+            while !self.line()?.starts_with(&last) {}
+        }
+
+        Ok((code, first))
+    }
+
+    fn line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+
+        if self.control.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(line.trim_end().to_string())
+    }
+
+    /// Runs a command whose answer comes over a passive data connection, returning all of it
+    fn transfer(
+        &mut self,
+        settings: &Settings,
+        line: &str,
+    ) -> Result<(Vec<u8>, bool), NetworkError> {
+        let (_, reply) = self.command("PASV")?;
+
+        let Some(port) = passive_port(&reply) else {
+            return Err(NetworkError::Ftp(self.host.clone(), reply));
+        };
+
+        // The address in the reply is often a private one behind NAT, the port is what counts
+        let mut addr = self
+            .control
+            .get_ref()
+            .peer_addr()
+            .map_err(|err| NetworkError::from_io(&self.host, err))?;
+
+        addr.set_port(port);
+
+        let data =
+            open_data(addr, settings).map_err(|err| NetworkError::from_io(&self.host, err))?;
+
+        self.send(line)?;
+
+        self.expect(1)?;
+
+        let body = read_capped(data, MAX_DOCUMENT_BYTES)
+            .map_err(|err| NetworkError::from_io(&self.host, err))?;
+
+        // A transfer cut short by the cap is aborted by the server when the socket closes
+        if !body.1 {
+            self.expect(2)?;
+        }
+
+        Ok(body)
+    }
+}
+
+fn open_data(addr: SocketAddr, settings: &Settings) -> io::Result<TcpStream> {
+    let data = resolver::connect(&[addr], settings.connect_timeout)?;
+
+    data.set_read_timeout(settings.read_timeout)?;
+
+    Ok(data)
+}
+
+/// The port of `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)`
+fn passive_port(reply: &str) -> Option<u16> {
+    let numbers = reply
+        .split(|c: char| !c.is_ascii_digit() && c != ',')
+        .find(|part| part.matches(',').count() == 5)?
+        .split(',')
+        .map(|number| number.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(u16::from(numbers[4]) << 8 | u16::from(numbers[5]))
+}
+
+/// An entry of a Unix style `LIST`, e.g. `drwxr-xr-x 2 ftp ftp 4096 Jan 01 2024 pub`
+///
+/// Lines in other formats are listed whole, as files of unknown size.
+fn parse_entry(line: &str) -> Option<Entry> {
+    let line = line.trim_end();
+
+    // `total 12` leads the listing of some servers
+    if line.is_empty() || line.starts_with("total ") {
+        return None;
+    }
+
+    let mut fields = line.split_whitespace();
+
+    let mode = fields.next()?;
+
+    let fields = fields.collect::<Vec<_>>();
+
+    if mode.len() != 10 || fields.len() < 8 {
+        return Some((false, line.to_string(), 0));
+    }
+
+    let len = fields[3].parse().unwrap_or(0);
+
+    // The name follows the date and may contain spaces, so it is what is left of the line
+    let mut name = line;
+
+    for _ in 0..8 {
+        name = name
+            .trim_start()
+            .trim_start_matches(|c: char| !c.is_whitespace());
+    }
+
+    let name = name.trim_start();
+
+    // A link names its target as `name -> target`
+    let name = match mode.starts_with('l') {
+        true => name.split(" -> ").next().unwrap_or(name),
+        false => name,
+    };
+
+    if matches!(name, "." | "..") {
+        return None;
+    }
+
+    Some((mode.starts_with('d'), name.to_string(), len))
+}
+
+/// Downloads a file, or lists a directory, over passive FTP
+pub fn retrieve(url: &Url, settings: &Settings) -> Result<Response, NetworkError> {
+    let mut session = Session::open(url, settings)?;
+
+    let path = percent_decode(url.path.split(['?', '#']).next().unwrap_or_default());
+
+    // Only a directory can be changed into, whether or not the URL ends with a slash
+    session.send(&format!("CWD {path}"))?;
+
+    let is_dir = session.reply()?.0 / 100 == 2;
+
+    let (headers, body, truncated) = if is_dir {
+        let (listing, truncated) = session.transfer(settings, "LIST")?;
+
+        let entries = String::from_utf8_lossy(&listing)
+            .lines()
+            .filter_map(parse_entry)
+            .collect::<Vec<_>>();
+
+        let origin = format!("ftp://{}", url.host);
+
+        let parent = Path::new(&path)
+            .parent()
+            .map(|parent| format!("{origin}{}", parent.display()));
+
+        let page = listing::index(
+            &format!("{origin}{path}"),
+            &path,
+            parent.as_deref(),
+            entries,
+        );
+
+        (
+            HashMap::from([("content-type".to_string(), "text/html".to_string())]),
+            page.into_bytes(),
+            truncated,
+        )
+    } else {
+        let (body, truncated) = session.transfer(settings, &format!("RETR {path}"))?;
+
+        let content_type = sniff(Path::new(&path), &body).to_string();
+
+        (
+            HashMap::from([("content-type".to_string(), content_type)]),
+            body,
+            truncated,
+        )
+    };
+
+    // The file is in hand, a server that hangs up first is no reason to fail
+    let _ = session.send("QUIT");
+
+    Ok(Response {
+        version: "".to_string(),
+        status_code: 200,
+        explanation: "OK".to_string(),
+        headers,
+        body,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn read_passive_ports() {
+        assert_eq!(
+            passive_port("227 Entering Passive Mode (192,168,1,2,19,137)"),
+            Some(19 * 256 + 137)
+        );
+        assert_eq!(passive_port("227 =10,0,0,1,4,1"), Some(1025));
+        assert_eq!(passive_port("227 Entering Passive Mode"), None);
+    }
+
+    #[test]
+    fn parse_unix_listings() {
+        assert_eq!(
+            parse_entry("drwxr-xr-x    2 ftp      ftp          4096 Jan 01  2024 pub"),
+            Some((true, "pub".to_string(), 4096))
+        );
+        assert_eq!(
+            parse_entry("-rw-r--r--   1 ftp  ftp   1234 Mar  5 12:30 read me.txt\r"),
+            Some((false, "read me.txt".to_string(), 1234))
+        );
+        assert_eq!(
+            parse_entry("lrwxrwxrwx   1 ftp  ftp      7 Mar  5 12:30 latest -> v1.2"),
+            Some((false, "latest".to_string(), 7))
+        );
+        assert_eq!(parse_entry("total 12"), None);
+        assert_eq!(
+            parse_entry("01-02-24  10:00AM       <DIR>          docs"),
+            Some((
+                false,
+                "01-02-24  10:00AM       <DIR>          docs".to_string(),
+                0
+            ))
+        );
+    }
+
+    /// Answers one session, sending `data` over the passive connection, and returns the commands
+    fn serve(data: &'static [u8], directory: bool) -> (SocketAddr, thread::JoinHandle<String>) {
+        let control = TcpListener::bind("127.0.0.1:0").unwrap();
+        let passive = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let addr = control.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = control.accept().unwrap();
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+
+            let port = passive.local_addr().unwrap().port();
+
+            writer.write_all(b"220-Welcome\r\n220 Ready\r\n").unwrap();
+
+            let mut commands = String::new();
+
+            loop {
+                let mut line = String::new();
+
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+
+                commands.push_str(&line);
+
+                let reply = match line.split_whitespace().next().unwrap() {
+                    "USER" => "331 Password please".to_string(),
+                    "PASS" => "230 Logged in".to_string(),
+                    "TYPE" => "200 Binary".to_string(),
+                    "CWD" if directory => "250 Okay".to_string(),
+                    "CWD" => "550 Not a directory".to_string(),
+                    "PASV" => format!(
+                        "227 Entering Passive Mode (10,0,0,1,{},{})",
+                        port >> 8,
+                        port & 0xff
+                    ),
+                    "RETR" | "LIST" => {
+                        writer.write_all(b"150 Opening\r\n").unwrap();
+
+                        let (mut socket, _) = passive.accept().unwrap();
+
+                        socket.write_all(data).unwrap();
+
+                        drop(socket);
+
+                        "226 Done".to_string()
+                    }
+                    _ => {
+                        writer.write_all(b"221 Bye\r\n").unwrap();
+
+                        break;
+                    }
+                };
+
+                writer.write_all(format!("{reply}\r\n").as_bytes()).unwrap();
+            }
+
+            commands
+        });
+
+        (addr, server)
+    }
+
+    #[test]
+    fn retrieve_files() {
+        let (addr, server) = serve(b"GIF89a\x01\x00", false);
+
+        let url = Url::new(&format!("ftp://{addr}/pub/dot%20file.gif")).unwrap();
+
+        let response = retrieve(&url, &Settings::default()).unwrap();
+
+        assert_eq!(response.body, b"GIF89a\x01\x00");
+        assert_eq!(response.headers["content-type"], "image/gif");
+        assert_eq!(
+            server.join().unwrap(),
+            "USER anonymous\r\nPASS voy@\r\nTYPE I\r\nCWD /pub/dot file.gif\r\n\
+             PASV\r\nRETR /pub/dot file.gif\r\nQUIT\r\n"
+        );
+    }
+
+    #[test]
+    fn list_directories() {
+        let (addr, server) = serve(
+            b"total 8\r\n-rw-r--r-- 1 ftp ftp 5 Jan 01 2024 b.txt\r\n\
+              drwxr-xr-x 2 ftp ftp 4096 Jan 01 2024 docs\r\n",
+            true,
+        );
+
+        let url = Url::new(&format!("ftp://jo:pw@{addr}/pub/")).unwrap();
+
+        let response = retrieve(&url, &Settings::default()).unwrap();
+
+        let mut page = String::new();
+
+        response.body.as_slice().read_to_string(&mut page).unwrap();
+
+        assert!(page.contains(&format!("<a href=\"ftp://{addr}/\">Parent directory</a>")));
+        assert!(page.contains(&format!(
+            "<p><a href=\"ftp://{addr}/pub/docs\">docs/</a></p>\n\
+             <p><a href=\"ftp://{addr}/pub/b.txt\">b.txt</a> (5 B)</p>"
+        )));
+        assert!(server.join().unwrap().starts_with("USER jo\r\nPASS pw\r\n"));
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::store::{self, now};
+use crate::{Scheme, Url};
+
+/// Pages beyond this many are forgotten, least frecent first
+const MAX_PAGES: usize = 1000;
+
+const DAY: u64 = 86_400;
+
+/// How often and how lately a page was loaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Visits {
+    count: u64,
+    /// Seconds since the Unix epoch
+    last: u64,
+}
+
+/// Pages loaded over HTTP, ranked for the new tab page
+#[derive(Default)]
+pub struct History {
+    pages: HashMap<String, Visits>,
+}
+
+/// `url` as typed, without the default port that `Url` spells out
+fn address(url: &Url) -> String {
+    let default_port = match url.scheme {
+        Scheme::Https => ":443",
+        _ => ":80",
+    };
+
+    let host = url.host.strip_suffix(default_port).unwrap_or(&url.host);
+
+    format!("{}://{host}{}", url.scheme.as_str(), url.path)
+}
+
+impl Visits {
+    /// Visits weighted by how recent the last one was, so a page once used daily fades out
+    fn frecency(&self, now: u64) -> u64 {
+        let weight = match now.saturating_sub(self.last) / DAY {
+            0..4 => 100,
+            4..14 => 70,
+            14..31 => 50,
+            31..90 => 30,
+            _ => 10,
+        };
+
+        self.count * weight
+    }
+}
+
+impl History {
+    /// `$XDG_DATA_HOME/voy/history.txt`, next to the cookie jar
+    pub fn default_path() -> Option<PathBuf> {
+        Some(store::data_dir()?.join("history.txt"))
+    }
+
+    /// Reads pages saved as `url<TAB>visits<TAB>last visit` lines
+    pub fn load(path: &Path) -> Self {
+        let mut history = History::default();
+
+        let Ok(source) = fs::read_to_string(path) else {
+            return history;
+        };
+
+        for line in source.lines().filter(|line| !line.starts_with('#')) {
+            let [url, count, last] = line.split('\t').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+
+            let (Ok(count), Ok(last)) = (count.parse::<u64>(), last.parse::<u64>()) else {
+                continue;
+            };
+
+            history
+                .pages
+                .insert(url.to_string(), Visits { count, last });
+        }
+
+        history
+    }
+
+    /// Writes the most frecent pages, dropping the rest
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut source = String::from("# Pages visited\n");
+
+        for (url, visits) in self.top(MAX_PAGES) {
+            source.push_str(&format!("{url}\t{}\t{}\n", visits.count, visits.last));
+        }
+
+        store::write_private(path, source.as_bytes())
+    }
+
+    /// Counts a visit to `url`, telling whether it was worth remembering
+    ///
+    /// Only web pages count; `file:`, `data:` and `about:` URLs say little about favourite sites.
+    pub fn record(&mut self, url: &Url) -> bool {
+        if !matches!(url.scheme, Scheme::Http | Scheme::Https) {
+            return false;
+        }
+
+        let visits = self
+            .pages
+            .entry(address(url))
+            .or_insert(Visits { count: 0, last: 0 });
+
+        visits.count += 1;
+        visits.last = now();
+
+        true
+    }
+
+    /// The `limit` most frecent pages, best first
+    fn top(&self, limit: usize) -> Vec<(&str, Visits)> {
+        let now = now();
+
+        let mut pages = self
+            .pages
+            .iter()
+            .map(|(url, visits)| (url.as_str(), *visits))
+            .collect::<Vec<_>>();
+
+        pages.sort_by(|(a_url, a), (b_url, b)| {
+            b.frecency(now)
+                .cmp(&a.frecency(now))
+                .then_with(|| a_url.cmp(b_url))
+        });
+
+        pages.truncate(limit);
+
+        pages
+    }
+
+    /// URLs of the `limit` most frecent pages, best first
+    pub fn top_sites(&self, limit: usize) -> Vec<&str> {
+        self.top(limit).into_iter().map(|(url, _)| url).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn rank_by_frecency() {
+        let now = now();
+
+        let mut history = History::default();
+
+        history.pages.insert(
+            "https://daily.example/".to_string(),
+            Visits {
+                count: 3,
+                last: now,
+            },
+        );
+        history.pages.insert(
+            "https://once-loved.example/".to_string(),
+            Visits {
+                count: 20,
+                last: now - 120 * DAY,
+            },
+        );
+        history.pages.insert(
+            "https://weekly.example/".to_string(),
+            Visits {
+                count: 3,
+                last: now - 7 * DAY,
+            },
+        );
+
+        assert!(history.record(&Url::new("https://weekly.example/").unwrap()));
+        assert!(!history.record(&Url::new("about:newtab").unwrap()));
+        assert_eq!(
+            address(&Url::new("http://localhost:8080/a").unwrap()),
+            "http://localhost:8080/a"
+        );
+
+        // 4 recent visits weigh 400, 3 recent ones 300 and 20 old ones 200
+        assert_eq!(
+            history.top_sites(2),
+            ["https://weekly.example/", "https://daily.example/"]
+        );
+
+        let path = env::temp_dir().join(format!("voy-history-{}.txt", std::process::id()));
+
+        history.save(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let loaded = History::load(&path);
+
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.top_sites(5), history.top_sites(5));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Request, Response};
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Continue,
+    Block,
+}
+
+/// Extension points called by the Client while loading a page
+pub trait Hook {
+    /// May add or change headers; returning `Verdict::Block` cancels the request
+    fn on_request(&self, _request: &mut Request) -> Verdict {
+        Verdict::Continue
+    }
+
+    /// Called for every response, including redirects, before it is used
+    fn on_response(&self, _response: &mut Response) {}
+
+    /// Called with the final document before it is displayed
+    fn on_dom_ready(&self, _document: &mut String) {}
+}
+
+/// Blocks requests to the given hosts and their subdomains
+pub struct BlockList(pub Vec<String>);
+
+impl Hook for BlockList {
+    fn on_request(&self, request: &mut Request) -> Verdict {
+        let hostname = request.url.hostname.to_lowercase();
+
+        let blocked = self.0.iter().any(|host| {
+            let host = host.to_lowercase();
+
+            hostname == host || hostname.ends_with(&format!(".{host}"))
+        });
+
+        if blocked {
+            Verdict::Block
+        } else {
+            Verdict::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Url;
+
+    #[test]
+    fn block_hosts() {
+        let blocklist = BlockList(vec!["ads.example".to_string()]);
+
+        let verdict = |url| blocklist.on_request(&mut Request::new(&Url::new(url).unwrap(), "GET"));
+
+        assert_eq!(verdict("https://ads.example/pixel"), Verdict::Block);
+        assert_eq!(verdict("https://cdn.ADS.example/pixel"), Verdict::Block);
+        assert_eq!(verdict("https://notads.example/"), Verdict::Continue);
+        assert_eq!(verdict("https://example.org/"), Verdict::Continue);
+    }
+}
@@ -0,0 +1,669 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::OnceLock;
+
+const DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// Every dynamic table entry costs its name and value plus this overhead (RFC 7541, 4.1)
+const ENTRY_OVERHEAD: usize = 32;
+
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Canonical Huffman code for every octet plus EOS, as (code, bit length) (RFC 7541, Appendix B)
+const HUFFMAN: [(u32, u8); 257] = [
+    (0x1ff8, 13),
+    (0x7fffd8, 23),
+    (0xfffffe2, 28),
+    (0xfffffe3, 28),
+    (0xfffffe4, 28),
+    (0xfffffe5, 28),
+    (0xfffffe6, 28),
+    (0xfffffe7, 28),
+    (0xfffffe8, 28),
+    (0xffffea, 24),
+    (0x3ffffffc, 30),
+    (0xfffffe9, 28),
+    (0xfffffea, 28),
+    (0x3ffffffd, 30),
+    (0xfffffeb, 28),
+    (0xfffffec, 28),
+    (0xfffffed, 28),
+    (0xfffffee, 28),
+    (0xfffffef, 28),
+    (0xffffff0, 28),
+    (0xffffff1, 28),
+    (0xffffff2, 28),
+    (0x3ffffffe, 30),
+    (0xffffff3, 28),
+    (0xffffff4, 28),
+    (0xffffff5, 28),
+    (0xffffff6, 28),
+    (0xffffff7, 28),
+    (0xffffff8, 28),
+    (0xffffff9, 28),
+    (0xffffffa, 28),
+    (0xffffffb, 28),
+    (0x14, 6),
+    (0x3f8, 10),
+    (0x3f9, 10),
+    (0xffa, 12),
+    (0x1ff9, 13),
+    (0x15, 6),
+    (0xf8, 8),
+    (0x7fa, 11),
+    (0x3fa, 10),
+    (0x3fb, 10),
+    (0xf9, 8),
+    (0x7fb, 11),
+    (0xfa, 8),
+    (0x16, 6),
+    (0x17, 6),
+    (0x18, 6),
+    (0x0, 5),
+    (0x1, 5),
+    (0x2, 5),
+    (0x19, 6),
+    (0x1a, 6),
+    (0x1b, 6),
+    (0x1c, 6),
+    (0x1d, 6),
+    (0x1e, 6),
+    (0x1f, 6),
+    (0x5c, 7),
+    (0xfb, 8),
+    (0x7ffc, 15),
+    (0x20, 6),
+    (0xffb, 12),
+    (0x3fc, 10),
+    (0x1ffa, 13),
+    (0x21, 6),
+    (0x5d, 7),
+    (0x5e, 7),
+    (0x5f, 7),
+    (0x60, 7),
+    (0x61, 7),
+    (0x62, 7),
+    (0x63, 7),
+    (0x64, 7),
+    (0x65, 7),
+    (0x66, 7),
+    (0x67, 7),
+    (0x68, 7),
+    (0x69, 7),
+    (0x6a, 7),
+    (0x6b, 7),
+    (0x6c, 7),
+    (0x6d, 7),
+    (0x6e, 7),
+    (0x6f, 7),
+    (0x70, 7),
+    (0x71, 7),
+    (0x72, 7),
+    (0xfc, 8),
+    (0x73, 7),
+    (0xfd, 8),
+    (0x1ffb, 13),
+    (0x7fff0, 19),
+    (0x1ffc, 13),
+    (0x3ffc, 14),
+    (0x22, 6),
+    (0x7ffd, 15),
+    (0x3, 5),
+    (0x23, 6),
+    (0x4, 5),
+    (0x24, 6),
+    (0x5, 5),
+    (0x25, 6),
+    (0x26, 6),
+    (0x27, 6),
+    (0x6, 5),
+    (0x74, 7),
+    (0x75, 7),
+    (0x28, 6),
+    (0x29, 6),
+    (0x2a, 6),
+    (0x7, 5),
+    (0x2b, 6),
+    (0x76, 7),
+    (0x2c, 6),
+    (0x8, 5),
+    (0x9, 5),
+    (0x2d, 6),
+    (0x77, 7),
+    (0x78, 7),
+    (0x79, 7),
+    (0x7a, 7),
+    (0x7b, 7),
+    (0x7ffe, 15),
+    (0x7fc, 11),
+    (0x3ffd, 14),
+    (0x1ffd, 13),
+    (0xffffffc, 28),
+    (0xfffe6, 20),
+    (0x3fffd2, 22),
+    (0xfffe7, 20),
+    (0xfffe8, 20),
+    (0x3fffd3, 22),
+    (0x3fffd4, 22),
+    (0x3fffd5, 22),
+    (0x7fffd9, 23),
+    (0x3fffd6, 22),
+    (0x7fffda, 23),
+    (0x7fffdb, 23),
+    (0x7fffdc, 23),
+    (0x7fffdd, 23),
+    (0x7fffde, 23),
+    (0xffffeb, 24),
+    (0x7fffdf, 23),
+    (0xffffec, 24),
+    (0xffffed, 24),
+    (0x3fffd7, 22),
+    (0x7fffe0, 23),
+    (0xffffee, 24),
+    (0x7fffe1, 23),
+    (0x7fffe2, 23),
+    (0x7fffe3, 23),
+    (0x7fffe4, 23),
+    (0x1fffdc, 21),
+    (0x3fffd8, 22),
+    (0x7fffe5, 23),
+    (0x3fffd9, 22),
+    (0x7fffe6, 23),
+    (0x7fffe7, 23),
+    (0xffffef, 24),
+    (0x3fffda, 22),
+    (0x1fffdd, 21),
+    (0xfffe9, 20),
+    (0x3fffdb, 22),
+    (0x3fffdc, 22),
+    (0x7fffe8, 23),
+    (0x7fffe9, 23),
+    (0x1fffde, 21),
+    (0x7fffea, 23),
+    (0x3fffdd, 22),
+    (0x3fffde, 22),
+    (0xfffff0, 24),
+    (0x1fffdf, 21),
+    (0x3fffdf, 22),
+    (0x7fffeb, 23),
+    (0x7fffec, 23),
+    (0x1fffe0, 21),
+    (0x1fffe1, 21),
+    (0x3fffe0, 22),
+    (0x1fffe2, 21),
+    (0x7fffed, 23),
+    (0x3fffe1, 22),
+    (0x7fffee, 23),
+    (0x7fffef, 23),
+    (0xfffea, 20),
+    (0x3fffe2, 22),
+    (0x3fffe3, 22),
+    (0x3fffe4, 22),
+    (0x7ffff0, 23),
+    (0x3fffe5, 22),
+    (0x3fffe6, 22),
+    (0x7ffff1, 23),
+    (0x3ffffe0, 26),
+    (0x3ffffe1, 26),
+    (0xfffeb, 20),
+    (0x7fff1, 19),
+    (0x3fffe7, 22),
+    (0x7ffff2, 23),
+    (0x3fffe8, 22),
+    (0x1ffffec, 25),
+    (0x3ffffe2, 26),
+    (0x3ffffe3, 26),
+    (0x3ffffe4, 26),
+    (0x7ffffde, 27),
+    (0x7ffffdf, 27),
+    (0x3ffffe5, 26),
+    (0xfffff1, 24),
+    (0x1ffffed, 25),
+    (0x7fff2, 19),
+    (0x1fffe3, 21),
+    (0x3ffffe6, 26),
+    (0x7ffffe0, 27),
+    (0x7ffffe1, 27),
+    (0x3ffffe7, 26),
+    (0x7ffffe2, 27),
+    (0xfffff2, 24),
+    (0x1fffe4, 21),
+    (0x1fffe5, 21),
+    (0x3ffffe8, 26),
+    (0x3ffffe9, 26),
+    (0xffffffd, 28),
+    (0x7ffffe3, 27),
+    (0x7ffffe4, 27),
+    (0x7ffffe5, 27),
+    (0xfffec, 20),
+    (0xfffff3, 24),
+    (0xfffed, 20),
+    (0x1fffe6, 21),
+    (0x3fffe9, 22),
+    (0x1fffe7, 21),
+    (0x1fffe8, 21),
+    (0x7ffff3, 23),
+    (0x3fffea, 22),
+    (0x3fffeb, 22),
+    (0x1ffffee, 25),
+    (0x1ffffef, 25),
+    (0xfffff4, 24),
+    (0xfffff5, 24),
+    (0x3ffffea, 26),
+    (0x7ffff4, 23),
+    (0x3ffffeb, 26),
+    (0x7ffffe6, 27),
+    (0x3ffffec, 26),
+    (0x3ffffed, 26),
+    (0x7ffffe7, 27),
+    (0x7ffffe8, 27),
+    (0x7ffffe9, 27),
+    (0x7ffffea, 27),
+    (0x7ffffeb, 27),
+    (0xffffffe, 28),
+    (0x7ffffec, 27),
+    (0x7ffffed, 27),
+    (0x7ffffee, 27),
+    (0x7ffffef, 27),
+    (0x7fffff0, 27),
+    (0x3ffffee, 26),
+    (0x3fffffff, 30),
+];
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("HPACK: {reason}"))
+}
+
+fn huffman_codes() -> &'static HashMap<(u8, u32), u16> {
+    static CODES: OnceLock<HashMap<(u8, u32), u16>> = OnceLock::new();
+
+    CODES.get_or_init(|| {
+        HUFFMAN
+            .iter()
+            .enumerate()
+            .map(|(symbol, &(code, len))| ((len, code), symbol as u16))
+            .collect()
+    })
+}
+
+fn huffman_decode(input: &[u8]) -> io::Result<Vec<u8>> {
+    let codes = huffman_codes();
+
+    let mut output = vec![];
+
+    let mut code = 0u32;
+    let mut len = 0u8;
+
+    for byte in input {
+        for shift in (0..8).rev() {
+            code = (code << 1) | u32::from((byte >> shift) & 1);
+            len += 1;
+
+            match codes.get(&(len, code)) {
+                Some(256) => return Err(invalid("EOS in string literal")),
+                Some(&symbol) => {
+                    output.push(symbol as u8);
+                    code = 0;
+                    len = 0;
+                }
+                None if len >= 30 => return Err(invalid("bad Huffman code")),
+                None => {}
+            }
+        }
+    }
+
+    // Leftover bits must be a short, all ones, prefix of EOS
+    if len > 7 || code != (1 << len) - 1 {
+        return Err(invalid("bad Huffman padding"));
+    }
+
+    Ok(output)
+}
+
+/// Reads an integer with an N-bit prefix (RFC 7541, 5.1)
+fn decode_integer(input: &mut &[u8], prefix: u8) -> io::Result<usize> {
+    let Some((&first, rest)) = input.split_first() else {
+        return Err(invalid("truncated integer"));
+    };
+
+    *input = rest;
+
+    let max = (1usize << prefix) - 1;
+
+    let mut value = usize::from(first) & max;
+
+    if value < max {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+
+    loop {
+        let Some((&byte, rest)) = input.split_first() else {
+            return Err(invalid("truncated integer"));
+        };
+
+        *input = rest;
+
+        if shift > 28 {
+            return Err(invalid("integer overflow"));
+        }
+
+        value += usize::from(byte & 0x7f) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+fn encode_integer(output: &mut Vec<u8>, flags: u8, prefix: u8, value: usize) {
+    let max = (1usize << prefix) - 1;
+
+    if value < max {
+        output.push(flags | value as u8);
+        return;
+    }
+
+    output.push(flags | max as u8);
+
+    let mut value = value - max;
+
+    while value >= 0x80 {
+        output.push((value % 0x80) as u8 | 0x80);
+        value /= 0x80;
+    }
+
+    output.push(value as u8);
+}
+
+fn decode_string(input: &mut &[u8]) -> io::Result<String> {
+    let huffman = input.first().is_some_and(|byte| byte & 0x80 != 0);
+
+    let len = decode_integer(input, 7)?;
+
+    if input.len() < len {
+        return Err(invalid("truncated string"));
+    }
+
+    let (bytes, rest) = input.split_at(len);
+
+    *input = rest;
+
+    let bytes = if huffman {
+        huffman_decode(bytes)?
+    } else {
+        bytes.to_vec()
+    };
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Header fields as literals without indexing, so the encoder needs no state
+pub fn encode(headers: &[(String, String)]) -> Vec<u8> {
+    let mut output = vec![];
+
+    for (name, value) in headers {
+        output.push(0);
+
+        encode_integer(&mut output, 0, 7, name.len());
+        output.extend(name.to_lowercase().as_bytes());
+
+        encode_integer(&mut output, 0, 7, value.len());
+        output.extend(value.as_bytes());
+    }
+
+    output
+}
+
+/// Header block decoder, whose dynamic table lives as long as the connection
+pub struct Decoder {
+    table: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: DEFAULT_TABLE_SIZE,
+        }
+    }
+}
+
+impl Decoder {
+    pub fn decode(&mut self, block: &[u8]) -> io::Result<Vec<(String, String)>> {
+        let mut input = block;
+
+        let mut headers = vec![];
+
+        while let Some(&first) = input.first() {
+            if first & 0x80 != 0 {
+                let index = decode_integer(&mut input, 7)?;
+
+                headers.push(self.entry(index)?);
+            } else if first & 0x40 != 0 {
+                let header = self.literal(&mut input, 6)?;
+
+                self.insert(header.clone());
+
+                headers.push(header);
+            } else if first & 0x20 != 0 {
+                self.max_size = decode_integer(&mut input, 5)?;
+
+                self.evict(0);
+            } else {
+                // Without indexing and never indexed only differ for intermediaries
+                headers.push(self.literal(&mut input, 4)?);
+            }
+        }
+
+        Ok(headers)
+    }
+
+    fn literal(&self, input: &mut &[u8], prefix: u8) -> io::Result<(String, String)> {
+        let index = decode_integer(input, prefix)?;
+
+        let name = if index == 0 {
+            decode_string(input)?
+        } else {
+            self.entry(index)?.0
+        };
+
+        Ok((name, decode_string(input)?))
+    }
+
+    fn entry(&self, index: usize) -> io::Result<(String, String)> {
+        if let Some(&(name, value)) = index.checked_sub(1).and_then(|i| STATIC_TABLE.get(i)) {
+            return Ok((name.to_owned(), value.to_owned()));
+        }
+
+        self.table
+            .get(index.wrapping_sub(STATIC_TABLE.len() + 1))
+            .cloned()
+            .ok_or_else(|| invalid("index out of range"))
+    }
+
+    fn insert(&mut self, header: (String, String)) {
+        let size = header.0.len() + header.1.len() + ENTRY_OVERHEAD;
+
+        self.evict(size);
+
+        // Entries larger than the whole table just empty it
+        if size <= self.max_size {
+            self.size += size;
+            self.table.push_front(header);
+        }
+    }
+
+    /// Makes room for an entry of `incoming` size
+    fn evict(&mut self, incoming: usize) {
+        while self.size + incoming > self.max_size {
+            let Some((name, value)) = self.table.pop_back() else {
+                break;
+            };
+
+            self.size -= name.len() + value.len() + ENTRY_OVERHEAD;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(input: &str) -> Vec<u8> {
+        let digits = input.replace(' ', "");
+
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn pairs(headers: &[(&str, &str)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn decode_integers() {
+        // RFC 7541, C.1
+        assert_eq!(decode_integer(&mut &[0x0a][..], 5).unwrap(), 10);
+        assert_eq!(
+            decode_integer(&mut &[0x1f, 0x9a, 0x0a][..], 5).unwrap(),
+            1337
+        );
+
+        let mut output = vec![];
+
+        encode_integer(&mut output, 0, 5, 1337);
+
+        assert_eq!(output, [0x1f, 0x9a, 0x0a]);
+    }
+
+    #[test]
+    fn decode_requests_with_huffman() {
+        // RFC 7541, C.4
+        let mut decoder = Decoder::default();
+
+        let headers = decoder
+            .decode(&hex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff"))
+            .unwrap();
+
+        assert_eq!(
+            headers,
+            pairs(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ])
+        );
+
+        let headers = decoder
+            .decode(&hex("8286 84be 5886 a8eb 1064 9cbf"))
+            .unwrap();
+
+        assert_eq!(headers[3], pairs(&[(":authority", "www.example.com")])[0]);
+        assert_eq!(headers[4], pairs(&[("cache-control", "no-cache")])[0]);
+
+        let headers = decoder
+            .decode(&hex(
+                "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+            ))
+            .unwrap();
+
+        assert_eq!(
+            headers,
+            pairs(&[
+                (":method", "GET"),
+                (":scheme", "https"),
+                (":path", "/index.html"),
+                (":authority", "www.example.com"),
+                ("custom-key", "custom-value"),
+            ])
+        );
+
+        assert_eq!(decoder.size, 164);
+    }
+
+    #[test]
+    fn round_trip_literals() {
+        let headers = pairs(&[(":status", "200"), ("Content-Type", "text/html")]);
+
+        let decoded = Decoder::default().decode(&encode(&headers)).unwrap();
+
+        assert_eq!(
+            decoded,
+            pairs(&[(":status", "200"), ("content-type", "text/html")])
+        );
+    }
+}
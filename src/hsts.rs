@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use crate::store::{self, now};
+use crate::{Response, Scheme, Url};
+
+/// A host that asked to be reached over HTTPS only, until `expires`
+#[derive(Debug)]
+struct Policy {
+    /// Seconds since the Unix epoch
+    expires: u64,
+    include_subdomains: bool,
+}
+
+/// `Strict-Transport-Security` policies, keyed by hostname
+#[derive(Default)]
+pub struct Hsts {
+    hosts: HashMap<String, Policy>,
+}
+
+impl Hsts {
+    /// `$XDG_DATA_HOME/voy/hsts.txt`, next to the cookie jar
+    pub fn default_path() -> Option<PathBuf> {
+        Some(store::data_dir()?.join("hsts.txt"))
+    }
+
+    /// Reads policies saved as `host<TAB>expires<TAB>includeSubDomains` lines
+    pub fn load(path: &Path) -> Self {
+        let mut hsts = Hsts::default();
+
+        let Ok(source) = fs::read_to_string(path) else {
+            return hsts;
+        };
+
+        for line in source.lines().filter(|line| !line.starts_with('#')) {
+            let [host, expires, subdomains] = line.split('\t').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+
+            let Ok(expires) = expires.parse::<u64>() else {
+                continue;
+            };
+
+            hsts.hosts.insert(
+                host.to_string(),
+                Policy {
+                    expires,
+                    include_subdomains: subdomains == "TRUE",
+                },
+            );
+        }
+
+        hsts
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let now = now();
+
+        let mut source = String::from("# HSTS hosts\n");
+
+        for (host, policy) in self.hosts.iter().filter(|(_, policy)| policy.expires > now) {
+            source.push_str(&format!(
+                "{host}\t{expires}\t{subdomains}\n",
+                expires = policy.expires,
+                subdomains = if policy.include_subdomains {
+                    "TRUE"
+                } else {
+                    "FALSE"
+                },
+            ));
+        }
+
+        fs::write(path, source)
+    }
+
+    /// Remembers the policy of a response, telling whether there was one to save
+    ///
+    /// Only secure responses count, lest anyone on the path pin a host to HTTPS or release it
+    pub fn store(&mut self, url: &Url, response: &Response) -> bool {
+        let Some(header) = response.headers.get("strict-transport-security") else {
+            return false;
+        };
+
+        if url.scheme != Scheme::Https || url.hostname.parse::<IpAddr>().is_ok() {
+            return false;
+        }
+
+        let mut max_age = None;
+        let mut include_subdomains = false;
+
+        for directive in header.split(';').map(str::trim) {
+            let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+
+            match name.trim().to_lowercase().as_str() {
+                "max-age" => max_age = value.trim().trim_matches('"').parse::<u64>().ok(),
+                "includesubdomains" => include_subdomains = true,
+                _ => {}
+            }
+        }
+
+        let Some(max_age) = max_age else {
+            return false;
+        };
+
+        let host = url.hostname.to_lowercase();
+
+        // `max-age=0` is how a host lifts its policy
+        if max_age == 0 {
+            return self.hosts.remove(&host).is_some();
+        }
+
+        self.hosts.insert(
+            host,
+            Policy {
+                expires: now().saturating_add(max_age),
+                include_subdomains,
+            },
+        );
+
+        true
+    }
+
+    /// Whether the host, or a parent including its subdomains, must be reached over HTTPS
+    pub fn is_known(&self, hostname: &str) -> bool {
+        let hostname = hostname.to_lowercase();
+
+        let now = now();
+
+        self.hosts.iter().any(|(host, policy)| {
+            policy.expires > now
+                && (*host == hostname
+                    || policy.include_subdomains && hostname.ends_with(&format!(".{host}")))
+        })
+    }
+
+    /// `http://` URLs to known hosts, rewritten to `https://`
+    pub fn upgrade(&self, url: Url) -> Url {
+        if url.scheme != Scheme::Http || !self.is_known(&url.hostname) {
+            return url;
+        }
+
+        // The default port moves along with the scheme, an explicit one stays
+        let authority = if url.port == 80 {
+            url.hostname.clone()
+        } else {
+            url.host.clone()
+        };
+
+        // Every part comes from a URL that already parsed, so this only fails in theory
+        let Ok(secure) = Url::new(&format!("https://{authority}{}", url.path)) else {
+            return url;
+        };
+
+        Url {
+            userinfo: url.userinfo,
+            ..secure
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn secure_response(header: &str) -> Response {
+        let mut response = Response::internal_page("OK", String::new());
+
+        response
+            .headers
+            .insert("strict-transport-security".to_string(), header.to_string());
+
+        response
+    }
+
+    #[test]
+    fn upgrade_known_hosts() {
+        let mut hsts = Hsts::default();
+
+        let secure = Url::new("https://example.org/").unwrap();
+
+        // Over plain HTTP the header is ignored
+        assert!(!hsts.store(
+            &Url::new("http://example.org/").unwrap(),
+            &secure_response("max-age=31536000")
+        ));
+
+        assert!(hsts.store(
+            &secure,
+            &secure_response("max-age=31536000; includeSubDomains")
+        ));
+
+        let upgraded = hsts.upgrade(Url::new("http://www.Example.org/login?next=/").unwrap());
+
+        assert_eq!(
+            upgraded.to_string(),
+            "https://www.Example.org:443/login?next=/"
+        );
+
+        let upgraded = hsts.upgrade(Url::new("http://example.org:8080/").unwrap());
+
+        assert_eq!(upgraded.to_string(), "https://example.org:8080/");
+
+        assert!(!hsts.is_known("notexample.org"));
+
+        hsts.store(&secure, &secure_response("max-age=0"));
+
+        assert_eq!(
+            hsts.upgrade(Url::new("http://example.org/").unwrap())
+                .scheme,
+            Scheme::Http
+        );
+    }
+
+    #[test]
+    fn persist_policies() {
+        let path = env::temp_dir().join(format!("voy-hsts-{}.txt", std::process::id()));
+
+        let mut hsts = Hsts::default();
+
+        hsts.store(
+            &Url::new("https://example.org/").unwrap(),
+            &secure_response("max-age=600"),
+        );
+
+        hsts.save(&path).unwrap();
+
+        let loaded = Hsts::load(&path);
+
+        let _ = fs::remove_file(&path);
+
+        assert!(loaded.is_known("example.org"));
+        assert!(!loaded.is_known("www.example.org"));
+    }
+}
@@ -0,0 +1,406 @@
+use std::io::{self, Read, Write};
+
+use crate::hpack;
+use crate::MAX_DOCUMENT_BYTES;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+const PRIORITY: u8 = 0x20;
+
+const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+
+/// Largest flow control window, so the server never waits on us
+const MAX_WINDOW: u32 = (1 << 31) - 1;
+const DEFAULT_WINDOW: u32 = 65_535;
+/// Largest frame a peer must accept before raising `SETTINGS_MAX_FRAME_SIZE`
+const MAX_FRAME_SIZE: usize = 16_384;
+
+/// Headers that only make sense for a single HTTP/1.1 hop, and `Expect`, as DATA follows at once
+const CONNECTION_HEADERS: [&str; 6] = [
+    "connection",
+    "expect",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream: u32,
+    payload: Vec<u8>,
+}
+
+/// A response as it came off an HTTP/2 stream, pseudo-headers included
+pub struct Message {
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub truncated: bool,
+}
+
+fn protocol_error(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("HTTP/2: {reason}"))
+}
+
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Frame> {
+    let mut header = [0u8; 9];
+
+    reader.read_exact(&mut header)?;
+
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+
+    let stream = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & MAX_WINDOW;
+
+    let mut payload = vec![0; len];
+
+    reader.read_exact(&mut payload)?;
+
+    Ok(Frame {
+        kind: header[3],
+        flags: header[4],
+        stream,
+        payload,
+    })
+}
+
+fn write_frame<W: Write>(
+    writer: &mut W,
+    kind: u8,
+    flags: u8,
+    stream: u32,
+    payload: &[u8],
+) -> io::Result<()> {
+    let len = (payload.len() as u32).to_be_bytes();
+
+    let mut frame = vec![len[1], len[2], len[3], kind, flags];
+
+    frame.extend(stream.to_be_bytes());
+    frame.extend(payload);
+
+    writer.write_all(&frame)
+}
+
+/// Strips padding, and priority fields from HEADERS, off a frame payload
+fn unpad(frame: &Frame) -> io::Result<&[u8]> {
+    let mut payload = &frame.payload[..];
+
+    let mut padding = 0;
+
+    if frame.flags & PADDED != 0 {
+        let Some((&len, rest)) = payload.split_first() else {
+            return Err(protocol_error("missing pad length"));
+        };
+
+        padding = usize::from(len);
+        payload = rest;
+    }
+
+    if frame.kind == HEADERS && frame.flags & PRIORITY != 0 {
+        payload = payload
+            .get(5..)
+            .ok_or_else(|| protocol_error("short priority"))?;
+    }
+
+    payload
+        .get(..payload.len().saturating_sub(padding))
+        .filter(|_| padding <= payload.len())
+        .ok_or_else(|| protocol_error("padding exceeds payload"))
+}
+
+/// Client side of one HTTP/2 connection, running one stream at a time
+pub struct Session {
+    decoder: hpack::Decoder,
+    next_stream: u32,
+    started: bool,
+    /// The server sent GOAWAY, so no new streams may be opened
+    pub closed: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            decoder: hpack::Decoder::default(),
+            next_stream: 1,
+            started: false,
+            closed: false,
+        }
+    }
+}
+
+impl Session {
+    fn start<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let mut settings = vec![];
+
+        for (id, value) in [
+            (SETTINGS_ENABLE_PUSH, 0),
+            (SETTINGS_INITIAL_WINDOW_SIZE, MAX_WINDOW),
+        ] {
+            settings.extend(id.to_be_bytes());
+            settings.extend(value.to_be_bytes());
+        }
+
+        writer.write_all(PREFACE)?;
+
+        write_frame(writer, SETTINGS, 0, 0, &settings)?;
+
+        // The connection window can only grow through WINDOW_UPDATE
+        write_frame(
+            writer,
+            WINDOW_UPDATE,
+            0,
+            0,
+            &(MAX_WINDOW - DEFAULT_WINDOW).to_be_bytes(),
+        )?;
+
+        self.started = true;
+
+        Ok(())
+    }
+
+    /// Sends a request on a new stream and reads its response
+    ///
+    /// The body goes out at once, so it should fit the server's initial 64KB window
+    pub fn exchange<S: Read + Write>(
+        &mut self,
+        stream: &mut S,
+        method: &str,
+        scheme: &str,
+        path: &str,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> io::Result<Message> {
+        if !self.started {
+            self.start(stream)?;
+        }
+
+        let id = self.next_stream;
+
+        self.next_stream += 2;
+
+        let authority = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default();
+
+        let mut fields = vec![
+            (":method".to_string(), method.to_string()),
+            (":scheme".to_string(), scheme.to_string()),
+            (":authority".to_string(), authority),
+            (":path".to_string(), path.to_string()),
+        ];
+
+        let forwarded = headers.iter().filter(|(name, _)| {
+            let name = name.to_lowercase();
+
+            name != "host" && !CONNECTION_HEADERS.contains(&name.as_str())
+        });
+
+        fields.extend(forwarded.cloned());
+
+        let flags = if body.is_empty() {
+            END_HEADERS | END_STREAM
+        } else {
+            END_HEADERS
+        };
+
+        write_frame(stream, HEADERS, flags, id, &hpack::encode(&fields))?;
+
+        let mut chunks = body.chunks(MAX_FRAME_SIZE).peekable();
+
+        while let Some(chunk) = chunks.next() {
+            let flags = if chunks.peek().is_none() {
+                END_STREAM
+            } else {
+                0
+            };
+
+            write_frame(stream, DATA, flags, id, chunk)?;
+        }
+
+        stream.flush()?;
+
+        self.receive(stream, id)
+    }
+
+    fn receive<S: Read + Write>(&mut self, stream: &mut S, id: u32) -> io::Result<Message> {
+        let mut message = Message {
+            headers: vec![],
+            body: vec![],
+            truncated: false,
+        };
+
+        let mut block = vec![];
+
+        loop {
+            let frame = read_frame(stream)?;
+
+            match frame.kind {
+                SETTINGS if frame.flags & ACK == 0 => write_frame(stream, SETTINGS, ACK, 0, &[])?,
+                PING if frame.flags & ACK == 0 => {
+                    write_frame(stream, PING, ACK, 0, &frame.payload)?
+                }
+                GOAWAY => {
+                    self.closed = true;
+
+                    let last = frame.payload.get(..4).map(|last| {
+                        u32::from_be_bytes([last[0], last[1], last[2], last[3]]) & MAX_WINDOW
+                    });
+
+                    if last.is_none_or(|last| last < id) {
+                        return Err(protocol_error("connection closed by server"));
+                    }
+                }
+                _ if frame.stream != id => {}
+                RST_STREAM => return Err(protocol_error("stream reset by server")),
+                HEADERS | CONTINUATION => {
+                    block.extend(unpad(&frame)?);
+
+                    if frame.flags & END_HEADERS != 0 {
+                        let headers = self.decoder.decode(&block)?;
+
+                        block.clear();
+
+                        // Interim 1xx responses are followed by the real one
+                        let interim = headers
+                            .iter()
+                            .any(|(name, value)| name == ":status" && value.starts_with('1'));
+
+                        if !interim {
+                            message.headers.extend(headers);
+                        }
+                    }
+                }
+                DATA => {
+                    // Give back what the frame used of the connection window
+                    if !frame.payload.is_empty() {
+                        let size = frame.payload.len() as u32;
+
+                        write_frame(stream, WINDOW_UPDATE, 0, 0, &size.to_be_bytes())?;
+                    }
+
+                    let data = unpad(&frame)?;
+
+                    if message.body.len() + data.len() > MAX_DOCUMENT_BYTES as usize {
+                        message.truncated = true;
+
+                        return Ok(message);
+                    }
+
+                    message.body.extend(data);
+                }
+                _ => {}
+            }
+
+            let ends_stream = matches!(frame.kind, HEADERS | DATA) && frame.flags & END_STREAM != 0;
+
+            if frame.stream == id && ends_stream {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn exchange_over_a_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut preface = [0u8; 24];
+
+            stream.read_exact(&mut preface).unwrap();
+
+            assert_eq!(preface, PREFACE);
+
+            write_frame(&mut stream, SETTINGS, 0, 0, &[]).unwrap();
+
+            let mut decoder = hpack::Decoder::default();
+
+            let request = loop {
+                let frame = read_frame(&mut stream).unwrap();
+
+                if frame.kind == HEADERS {
+                    break decoder.decode(&frame.payload).unwrap();
+                }
+            };
+
+            let status = hpack::encode(&[(":status".to_string(), "200".to_string())]);
+
+            write_frame(&mut stream, HEADERS, END_HEADERS, 1, &status).unwrap();
+            write_frame(&mut stream, PING, 0, 0, b"12345678").unwrap();
+            write_frame(&mut stream, DATA, 0, 1, b"Hello, ").unwrap();
+            write_frame(&mut stream, DATA, PADDED | END_STREAM, 1, b"\x02h2!\0\0").unwrap();
+
+            // The client answers PING before the stream ends
+            let pong = loop {
+                let frame = read_frame(&mut stream).unwrap();
+
+                if frame.kind == PING {
+                    break frame;
+                }
+            };
+
+            assert_eq!(pong.flags, ACK);
+
+            request
+        });
+
+        let mut stream = BufReader::new(TcpStream::connect(addr).unwrap());
+
+        let mut session = Session::default();
+
+        let headers = [
+            ("Host".to_string(), "example.org".to_string()),
+            ("Connection".to_string(), "keep-alive".to_string()),
+        ];
+
+        let message = session
+            .exchange(
+                stream.get_mut(),
+                "GET",
+                "https",
+                "/index.html",
+                &headers,
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            message.headers,
+            [(":status".to_string(), "200".to_string())]
+        );
+        assert_eq!(message.body, b"Hello, h2!");
+
+        let request = server.join().unwrap();
+
+        assert!(request.contains(&(":authority".to_string(), "example.org".to_string())));
+        assert!(request.contains(&(":path".to_string(), "/index.html".to_string())));
+        assert!(!request.iter().any(|(name, _)| name == "connection"));
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+const DEFAULT_LOCALE: &str = "en";
+
+const LOCALES: &[(&str, &str)] = &[("en", include_str!("../locales/en.in"))];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+struct Catalog {
+    messages: HashMap<&'static str, &'static str>,
+    fallback: HashMap<&'static str, &'static str>,
+}
+
+impl Catalog {
+    fn parse(source: &'static str) -> HashMap<&'static str, &'static str> {
+        source
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once(' '))
+            .map(|(key, message)| (key, message.trim()))
+            .collect()
+    }
+
+    fn new(locale: &str) -> Self {
+        let source = |name: &str| {
+            LOCALES
+                .iter()
+                .find(|(candidate, _)| candidate.eq_ignore_ascii_case(name))
+                .map(|(_, source)| *source)
+        };
+
+        let Some(fallback) = source(DEFAULT_LOCALE) else {
+            panic!("Missing {DEFAULT_LOCALE} locale");
+        };
+
+        let messages = Self::candidates(locale)
+            .iter()
+            .find_map(|name| source(name))
+            .unwrap_or(fallback);
+
+        Catalog {
+            messages: Self::parse(messages),
+            fallback: Self::parse(fallback),
+        }
+    }
+
+    // sv_SE.UTF-8@euro -> [sv_SE, sv]
+    fn candidates(locale: &str) -> Vec<String> {
+        let locale = locale
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('-', "_");
+
+        let mut candidates = vec![locale.clone()];
+
+        if let Some((language, _)) = locale.split_once('_') {
+            candidates.push(language.to_owned());
+        }
+
+        candidates
+    }
+
+    fn get(&self, key: &str) -> String {
+        self.messages
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or_else(|| key.to_owned(), |message| message.to_string())
+    }
+}
+
+fn from_env() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_owned())
+}
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new(&from_env()))
+}
+
+/// Overrides the locale picked up from the environment, must run before any lookup
+pub fn set_locale(locale: &str) {
+    let _ = CATALOG.set(Catalog::new(locale));
+}
+
+/// The user's language, e.g. `sv` for `sv_SE.UTF-8`, from `locale` or else the environment
+pub fn language(locale: Option<&str>) -> String {
+    let locale = locale.map_or_else(from_env, str::to_owned);
+
+    match Catalog::candidates(&locale).pop() {
+        Some(language) if !matches!(language.as_str(), "" | "C" | "POSIX") => {
+            language.to_lowercase()
+        }
+        _ => DEFAULT_LOCALE.to_owned(),
+    }
+}
+
+pub fn tr(key: &str) -> String {
+    catalog().get(key)
+}
+
+/// Looks up a message and fills in its `{name}` placeholders
+pub fn tr_with(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(tr(key), |message, (name, value)| {
+        message.replace(&format!("{{{name}}}"), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_candidates() {
+        assert_eq!(Catalog::candidates("sv_SE.UTF-8@euro"), ["sv_SE", "sv"]);
+        assert_eq!(Catalog::candidates("pt-BR"), ["pt_BR", "pt"]);
+        assert_eq!(Catalog::candidates("en"), ["en"]);
+
+        assert_eq!(language(Some("pt-BR")), "pt");
+        assert_eq!(language(Some("C.UTF-8")), "en");
+    }
+
+    #[test]
+    fn lookup_falls_back_to_english() {
+        let catalog = Catalog::new("xx_XX");
+
+        assert_eq!(catalog.get("retry-prompt"), "Retry?");
+        assert_eq!(catalog.get("not-a-key"), "not-a-key");
+    }
+
+    #[test]
+    fn template_covers_every_message() {
+        let english = Catalog::parse(include_str!("../locales/en.in"));
+
+        let template = include_str!("../locales/template.in")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+
+        assert_eq!(english.len(), template.len());
+        assert!(template.iter().all(|key| english.contains_key(key)));
+    }
+}
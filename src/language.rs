@@ -0,0 +1,330 @@
+use std::cmp::Reverse;
+use std::io::Write;
+use std::ops::Range;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::about::escape;
+use crate::entity::EntityParser;
+use crate::hooks::Hook;
+use crate::placeholder::attribute;
+
+/// Words common enough in each language to tell it apart in a few sentences
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "that", "it", "was", "for", "with", "are", "this",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "que", "y", "por", "una", "es", "del", "con", "para", "como",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "les", "et", "des", "est", "une", "que", "pour", "dans", "pas", "qui", "sur",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "zu", "mit", "sich", "auf", "den",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "que", "e", "do", "da", "em", "um", "para", "não", "uma", "com",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "e", "per", "non", "un", "una", "sono", "del", "gli", "della",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "van", "en", "is", "dat", "niet", "op", "te", "zijn", "met",
+        ],
+    ),
+    (
+        "sv",
+        &[
+            "och", "att", "det", "som", "en", "är", "på", "för", "med", "inte", "av", "jag",
+        ],
+    ),
+];
+
+/// Stopwords a guess needs, so a couple of words in passing do not decide it
+const MIN_STOPWORDS: usize = 3;
+
+/// The language of a document, e.g. `pt-BR`, from `<html lang>` or else guessed from its words
+pub fn detect(document: &str) -> Option<String> {
+    declared(document).or_else(|| guess(document).map(str::to_string))
+}
+
+fn declared(document: &str) -> Option<String> {
+    let lowercase = document.to_ascii_lowercase();
+
+    let start = lowercase
+        .match_indices("<html")
+        .map(|(start, _)| start)
+        .find(|&start| {
+            lowercase[start + 5..].starts_with(|c: char| c.is_whitespace() || c == '>')
+        })?;
+
+    let end = start + document[start..].find('>')?;
+
+    attribute(&document[start + 1..end], "lang")
+        .map(|lang| lang.trim().to_string())
+        .filter(|lang| !lang.is_empty())
+}
+
+/// The language whose stopwords the text uses most, when it clearly leads
+fn guess(document: &str) -> Option<&'static str> {
+    let entity_parser = EntityParser::new();
+
+    let words = text_nodes(document)
+        .into_iter()
+        .map(|node| entity_parser.decode(&document[node]).to_lowercase())
+        .collect::<Vec<_>>();
+
+    let words = words
+        .iter()
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut scores = STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let score = words.iter().filter(|word| stopwords.contains(word)).count();
+
+            (score, *language)
+        })
+        .collect::<Vec<_>>();
+
+    scores.sort_by_key(|(score, _)| Reverse(*score));
+
+    match scores.as_slice() {
+        [(best, language), (second, _), ..] if *best >= MIN_STOPWORDS && best > second => {
+            Some(language)
+        }
+        _ => None,
+    }
+}
+
+/// Where the text between tags lies, leaving out blank runs, comments, scripts and styles
+fn text_nodes(document: &str) -> Vec<Range<usize>> {
+    let lowercase = document.to_ascii_lowercase();
+
+    let mut nodes = vec![];
+
+    let mut position = 0;
+
+    while position < document.len() {
+        let start = document[position..]
+            .find('<')
+            .map_or(document.len(), |start| position + start);
+
+        if !document[position..start].trim().is_empty() {
+            nodes.push(position..start);
+        }
+
+        if start == document.len() {
+            break;
+        }
+
+        let rest = &lowercase[start..];
+
+        // Comments and raw text may hold `<` and `>` of their own
+        let close = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<script") {
+            "</script>"
+        } else if rest.starts_with("<style") {
+            "</style>"
+        } else {
+            ">"
+        };
+
+        position = rest
+            .find(close)
+            .map_or(document.len(), |end| start + end + close.len());
+    }
+
+    nodes
+}
+
+/// Rewrites the text of pages in another language through a translation command
+///
+/// The command reads one text node per line on stdin and writes as many lines back, with
+/// `VOY_SOURCE_LANG` and `VOY_TARGET_LANG` set; it may call out to any translation API.
+pub struct Translator {
+    pub command: String,
+    /// Language pages are translated into, e.g. `en`
+    pub target: String,
+}
+
+impl Translator {
+    /// The translated lines, or `None` when the command fails or loses lines
+    fn run(&self, source: &str, lines: &[String]) -> Option<Vec<String>> {
+        let mut parts = self.command.split_whitespace();
+
+        let mut child = Command::new(parts.next()?)
+            .args(parts)
+            .env("VOY_SOURCE_LANG", source)
+            .env("VOY_TARGET_LANG", &self.target)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+
+        let mut input = lines.join("\n");
+
+        input.push('\n');
+
+        // Written from another thread, so a command answering as it reads cannot block on us
+        let mut stdin = child.stdin.take()?;
+
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child.wait_with_output().ok()?;
+
+        writer.join().ok()?.ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let translated = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        (translated.len() == lines.len()).then_some(translated)
+    }
+}
+
+/// The primary subtag, e.g. `pt` for `pt-BR`
+fn primary(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+impl Hook for Translator {
+    fn on_dom_ready(&self, document: &mut String) {
+        let Some(source) = detect(document) else {
+            return;
+        };
+
+        if primary(&source) == primary(&self.target) {
+            return;
+        }
+
+        let entity_parser = EntityParser::new();
+
+        let nodes = text_nodes(document);
+
+        // Lines are the unit of exchange, so a node's own line breaks become spaces
+        let lines = nodes
+            .iter()
+            .map(|node| {
+                entity_parser
+                    .decode(document[node.clone()].trim())
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+
+        let Some(translated) = self.run(&source, &lines) else {
+            return;
+        };
+
+        // Replaced from the end, so the ranges still ahead stay valid
+        for (node, text) in nodes.into_iter().zip(translated).rev() {
+            let original = &document[node.clone()];
+
+            let leading = &original[..original.len() - original.trim_start().len()];
+
+            let trailing = &original[original.trim_end().len()..];
+
+            let replacement = format!("{leading}{}{trailing}", escape(&text));
+
+            document.replace_range(node, &replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_languages() {
+        assert_eq!(
+            detect("<!DOCTYPE html><HTML class=x lang=\"pt-BR\"><p>Hello</p></HTML>").as_deref(),
+            Some("pt-BR")
+        );
+        assert_eq!(
+            detect("<p>Der Hund und die Katze sind nicht zu Hause, das ist klar.</p>").as_deref(),
+            Some("de")
+        );
+        assert_eq!(
+            detect("<p>Le chat et le chien sont dans la maison.</p><script>the and of to</script>")
+                .as_deref(),
+            Some("fr")
+        );
+        assert_eq!(detect("<p>Hello world</p>"), None);
+    }
+
+    #[test]
+    fn find_text_nodes() {
+        let document =
+            "<p>One &amp; two</p>\n<!-- <b>no</b> --><style>p > a {}</style> <i>three</i>";
+
+        let nodes = text_nodes(document)
+            .into_iter()
+            .map(|node| &document[node])
+            .collect::<Vec<_>>();
+
+        assert_eq!(nodes, ["One &amp; two", "three"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn translate_text_nodes() {
+        let translator = Translator {
+            command: "tr a-z A-Z".to_string(),
+            target: "en".to_string(),
+        };
+
+        let mut document =
+            "<html lang=\"es\"><p> Hola &amp; adiós </p>\n<a href=\"/x\">amigo\nmío</a></html>"
+                .to_string();
+
+        translator.on_dom_ready(&mut document);
+
+        assert_eq!(
+            document,
+            "<html lang=\"es\"><p> HOLA &amp; ADIóS </p>\n<a href=\"/x\">AMIGO MíO</a></html>"
+        );
+
+        // Pages already in the target language are left alone
+        let mut document = "<html lang=\"en-GB\"><p>Hello</p></html>".to_string();
+
+        translator.on_dom_ready(&mut document);
+
+        assert_eq!(document, "<html lang=\"en-GB\"><p>Hello</p></html>");
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::about::escape;
+use crate::download::size;
+use crate::i18n::{tr, tr_with};
+
+/// A listed entry: whether it is a directory, its name and its size in bytes
+pub type Entry = (bool, String, u64);
+
+/// An HTML index of a directory, subdirectories first, each entry linking to its `file:` URL
+pub fn render(dir: &Path) -> io::Result<String> {
+    let entries = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            // Links are followed, so a link to a directory lists as one
+            let metadata = fs::metadata(entry.path()).ok();
+
+            let is_dir = metadata.as_ref().is_some_and(|metadata| metadata.is_dir());
+
+            let len = metadata.map_or(0, |metadata| metadata.len());
+
+            (
+                is_dir,
+                entry.file_name().to_string_lossy().into_owned(),
+                len,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let shown = dir.display().to_string();
+
+    let base = shown.trim_end_matches('/');
+
+    let parent = dir
+        .parent()
+        .map(|parent| format!("file://{}", parent.display()));
+
+    Ok(index(
+        &format!("file://{base}"),
+        base,
+        parent.as_deref(),
+        entries,
+    ))
+}
+
+/// An HTML index of `path`, whose entries are linked to below `url`, e.g. an FTP directory
+pub fn index(url: &str, path: &str, parent: Option<&str>, mut entries: Vec<Entry>) -> String {
+    entries.sort_by(|(a_dir, a_name, _), (b_dir, b_name, _)| {
+        b_dir
+            .cmp(a_dir)
+            .then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+    });
+
+    let url = url.trim_end_matches('/');
+
+    let path = path.trim_end_matches('/');
+
+    let title = escape(&tr_with("listing-title", &[("path", &format!("{path}/"))]));
+
+    let mut body = format!("<html><body>\n<h1>{title}</h1>\n");
+
+    if let Some(parent) = parent {
+        body.push_str(&format!(
+            "<p><a href=\"{}\">{}</a></p>\n",
+            escape(parent),
+            tr("listing-parent")
+        ));
+    }
+
+    if entries.is_empty() {
+        body.push_str(&format!("<p>{}</p>\n", tr("listing-empty")));
+    }
+
+    for (is_dir, name, len) in entries {
+        let href = escape(&format!("{url}/{name}"));
+
+        let line = match is_dir {
+            true => format!("<a href=\"{href}\">{}/</a>", escape(&name)),
+            false => format!("<a href=\"{href}\">{}</a> ({})", escape(&name), size(len)),
+        };
+
+        body.push_str(&format!("<p>{line}</p>\n"));
+    }
+
+    body.push_str("</body></html>");
+
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn list_directories() {
+        let dir = env::temp_dir().join(format!("voy-listing-{}", std::process::id()));
+
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("b.txt"), "12345").unwrap();
+        fs::write(dir.join("A <1>.html"), "").unwrap();
+
+        let listing = render(&dir);
+
+        let _ = fs::remove_dir_all(&dir);
+
+        let listing = listing.unwrap();
+
+        let base = dir.display();
+
+        assert!(listing.contains(&format!("<h1>Index of {base}/</h1>")));
+        assert!(listing.contains("Parent directory"));
+
+        let lines = listing
+            .lines()
+            .filter(|line| line.starts_with("<p><a href=\"file://") && !line.contains("Parent"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [
+                format!("<p><a href=\"file://{base}/docs\">docs/</a></p>"),
+                format!(
+                    "<p><a href=\"file://{base}/A &lt;1&gt;.html\">A &lt;1&gt;.html</a> (0 B)</p>"
+                ),
+                format!("<p><a href=\"file://{base}/b.txt\">b.txt</a> (5 B)</p>"),
+            ]
+        );
+    }
+}
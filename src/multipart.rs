@@ -0,0 +1,218 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Magic numbers of the formats worth naming, checked before the file extension
+const SIGNATURES: [(&[u8], &str); 7] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF8", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\0asm", "application/wasm"),
+];
+
+const EXTENSIONS: [(&str, &str); 10] = [
+    ("txt", "text/plain"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("csv", "text/csv"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+];
+
+/// One field of a `multipart/form-data` body, from `--form name=value` or `--form name=@file`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    /// Set for uploads, as the name the server sees
+    pub filename: Option<String>,
+    /// Left out for plain values, which servers read as text
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    pub fn text(name: &str, value: &str) -> Self {
+        Part {
+            name: name.to_string(),
+            filename: None,
+            content_type: None,
+            data: value.as_bytes().to_vec(),
+        }
+    }
+
+    /// Reads an upload, sniffing its type unless given one as in `photo.jpg;type=image/jpeg`
+    pub fn file(name: &str, path: &str, content_type: Option<&str>) -> io::Result<Self> {
+        let path = Path::new(path);
+
+        let data = fs::read(path)?;
+
+        Ok(Part {
+            name: name.to_string(),
+            filename: path
+                .file_name()
+                .map(|filename| filename.to_string_lossy().into_owned()),
+            content_type: Some(
+                content_type
+                    .map(str::to_string)
+                    .unwrap_or_else(|| sniff(path, &data).to_string()),
+            ),
+            data,
+        })
+    }
+}
+
+/// The media type of a file, from its first bytes, then its extension
+pub fn sniff(path: &Path, data: &[u8]) -> &'static str {
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return content_type;
+    }
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if let Some((_, content_type)) = EXTENSIONS.iter().find(|(known, _)| *known == extension) {
+        return content_type;
+    }
+
+    if std::str::from_utf8(data).is_ok() && !data.contains(&0) {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Quotes and line breaks would end a parameter early, so they are written the way browsers do
+fn quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// A boundary found in none of the parts
+fn boundary(parts: &[Part]) -> String {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default()
+        ^ u128::from(std::process::id());
+
+    (0..)
+        .map(|attempt: u128| format!("voy-{:032x}", seed.wrapping_mul(attempt + 1)))
+        .find(|boundary| {
+            !parts
+                .iter()
+                .any(|part| contains(&part.data, boundary.as_bytes()))
+        })
+        .unwrap_or_default()
+}
+
+/// The `Content-Type`, naming the boundary, and the body for `parts`
+pub fn encode(parts: &[Part]) -> (String, Vec<u8>) {
+    let boundary = boundary(parts);
+
+    let mut body = vec![];
+
+    for part in parts {
+        let mut head = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"",
+            quote(&part.name)
+        );
+
+        if let Some(filename) = &part.filename {
+            head.push_str(&format!("; filename=\"{}\"", quote(filename)));
+        }
+
+        if let Some(content_type) = &part.content_type {
+            head.push_str(&format!("\r\nContent-Type: {content_type}"));
+        }
+
+        head.push_str("\r\n\r\n");
+
+        body.extend(head.as_bytes());
+        body.extend(&part.data);
+        body.extend(b"\r\n");
+    }
+
+    body.extend(format!("--{boundary}--\r\n").as_bytes());
+
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn sniff_uploads() {
+        let path = env::temp_dir().join(format!("voy-upload-{}.bin", std::process::id()));
+
+        fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let part = Part::file("avatar", path.to_str().unwrap(), None).unwrap();
+
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(part.content_type.as_deref(), Some("image/png"));
+        assert_eq!(
+            part.filename,
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        );
+
+        assert_eq!(sniff(Path::new("notes.md"), b"# Notes"), "text/plain");
+        assert_eq!(sniff(Path::new("data.JSON"), b"{}"), "application/json");
+        assert_eq!(
+            sniff(Path::new("blob"), b"\0\x01\x02"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn encode_parts() {
+        let parts = [
+            Part::text("title", "Hello \"world\""),
+            Part {
+                name: "file".to_string(),
+                filename: Some("a.txt".to_string()),
+                content_type: Some("text/plain".to_string()),
+                data: b"line\r\n".to_vec(),
+            },
+        ];
+
+        let (content_type, body) = encode(&parts);
+
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+
+        let expected = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHello \"world\"\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nline\r\n\r\n\
+             --{boundary}--\r\n"
+        );
+
+        assert_eq!(String::from_utf8(body).unwrap(), expected);
+        assert_eq!(quote("a\"b\nc"), "a%22b%0Ac");
+    }
+}
@@ -0,0 +1,481 @@
+use native_tls::Protocol;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::charset::Charset;
+use crate::i18n::tr_with;
+use crate::multipart::Part;
+use crate::throttle;
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub url: Option<String>,
+    pub edit: bool,
+    /// Prints an outline of the headings instead of the page
+    pub toc: bool,
+    /// Prints word count, reading time and page weight instead of the page
+    pub stats: bool,
+    pub external: bool,
+    /// Partly typed address to list suggestions for, opening the one picked
+    pub complete: Option<String>,
+    /// Page opened when no URL is given: `blank`, `newtab` or a URL, instead of `VOY_HOME`
+    pub home: Option<String>,
+    /// Programs launched for schemes the browser cannot load itself, keyed by scheme
+    pub handlers: HashMap<String, String>,
+    /// Overrides the locale taken from `LANG` for user-facing messages
+    pub lang: Option<String>,
+    /// Command rewriting the text of pages in other languages, e.g. `trans -b :en`
+    pub translate: Option<String>,
+    /// Language pages are translated into, instead of the one of `--lang` or `LANG`
+    pub translate_to: Option<String>,
+    /// Reads pages in this encoding whatever they declare, e.g. `windows-1252`
+    pub charset: Option<Charset>,
+    pub max_redirects: Option<usize>,
+    /// Attempts after a transient failure or a `502`/`503`, zero to give up right away
+    pub retries: Option<u32>,
+    pub do_not_track: bool,
+    pub global_privacy_control: bool,
+    /// Hosts, including their subdomains, that are never contacted
+    pub blocked_hosts: Vec<String>,
+    /// Speaks HTTP/2 with servers that accept it, falling back to HTTP/1.1
+    pub http2: bool,
+    /// Sends HTTP/1.0 requests, to debug servers that predate HTTP/1.1
+    pub http1_0: bool,
+    /// Keeps cookies for this run only, neither reading nor writing the cookie file
+    pub no_persist_cookies: bool,
+    /// Sends every request through this HTTP proxy, e.g. `http://proxy.example:3128`
+    pub proxy: Option<String>,
+    /// Sent instead of `GET`, e.g. `HEAD` or `DELETE`, from `-X`
+    pub method: Option<String>,
+    /// Pieces of the body to POST, joined with `&`, or run together for `--json`
+    pub data: Vec<String>,
+    /// Sends `data` as JSON rather than as a form
+    pub json: bool,
+    /// Fields of a `multipart/form-data` body, files included
+    pub form: Vec<Part>,
+    /// Sent with every request, `Name: value` from `-H`; an empty value drops a default header
+    pub headers: Vec<(String, String)>,
+    /// `user:password` answering Basic and Digest challenges
+    pub user: Option<(String, String)>,
+    /// Token sent as `Authorization: Bearer` to the origin a load starts from
+    pub bearer: Option<String>,
+    /// Certificate, and the key when kept apart, presented to each host for mutual TLS
+    pub client_certs: HashMap<String, (PathBuf, Option<PathBuf>)>,
+    /// Client certificate for every host not given one with `--client-cert`
+    pub cert: Option<PathBuf>,
+    /// Key for `--cert`, when not in the same file
+    pub key: Option<PathBuf>,
+    /// Unlocks PKCS#12 client certificates
+    pub cert_password: Option<String>,
+    /// Extra roots to trust, e.g. a company CA
+    pub cacerts: Vec<PathBuf>,
+    /// Oldest TLS version to accept
+    pub tls_min: Option<Protocol>,
+    /// Accepts invalid certificates, warning loudly
+    pub insecure: bool,
+    /// Prints the server certificate instead of the page
+    pub show_cert: bool,
+    /// Always connects to the origin, ignoring `Alt-Svc`
+    pub no_alt_svc: bool,
+    /// Socket file every request is sent over, e.g. `/var/run/docker.sock`
+    pub unix_socket: Option<PathBuf>,
+    /// `host:port` pairs connected to instead of the origin, keyed by the origin's `host:port`
+    pub connect_to: HashMap<String, String>,
+    /// DNS-over-HTTPS endpoint resolving hostnames instead of the system resolver
+    pub doh: Option<String>,
+    /// Seconds to wait for a connection, a read, or the whole page; zero waits forever
+    pub connect_timeout: Option<u64>,
+    pub read_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    /// Where every request and response is written for debugging, one numbered set of files each
+    pub trace_dir: Option<PathBuf>,
+    /// Simulated bandwidth in bytes per second, from `--throttle 256kbps`
+    pub throttle: Option<u64>,
+    /// Simulated round-trip time, from `--latency 200ms`
+    pub latency: Option<Duration>,
+    /// File the body is saved to instead of being shown, from `-o`
+    pub output: Option<PathBuf>,
+    /// Saves the body under the last segment of the URL, from `-O`
+    pub remote_name: bool,
+}
+
+impl Options {
+    pub fn parse<I>(mut args: I) -> Result<Self, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--edit" => options.edit = true,
+                "--toc" => options.toc = true,
+                "--stats" => options.stats = true,
+                "--external" => options.external = true,
+                "--dnt" => options.do_not_track = true,
+                "--gpc" => options.global_privacy_control = true,
+                "--http2" => options.http2 = true,
+                "--http1.0" => options.http1_0 = true,
+                "--insecure" => options.insecure = true,
+                "--show-cert" => options.show_cert = true,
+                "--no-alt-svc" => options.no_alt_svc = true,
+                "--no-persist-cookies" => options.no_persist_cookies = true,
+                "-O" | "--remote-name" => options.remote_name = true,
+                "-o" | "--output" => {
+                    options.output = Some(PathBuf::from(Self::value(&arg, args.next())?))
+                }
+                "--handler" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((scheme, command)) = value.split_once('=') else {
+                        return Err(tr_with("expected-handler", &[("option", &arg)]));
+                    };
+
+                    options
+                        .handlers
+                        .insert(scheme.to_lowercase(), command.to_owned());
+                }
+                "--block" => options.blocked_hosts.push(Self::value(&arg, args.next())?),
+                "--user" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((user, password)) = value.split_once(':') else {
+                        return Err(tr_with("expected-credentials", &[("option", &arg)]));
+                    };
+
+                    options.user = Some((user.to_string(), password.to_string()));
+                }
+                "--client-cert" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((host, files)) = value
+                        .split_once('=')
+                        .filter(|(host, files)| !host.is_empty() && !files.is_empty())
+                    else {
+                        return Err(tr_with("expected-client-cert", &[("option", &arg)]));
+                    };
+
+                    let (cert, key) = match files.split_once(',') {
+                        Some((cert, key)) => (cert, Some(PathBuf::from(key))),
+                        None => (files, None),
+                    };
+
+                    options
+                        .client_certs
+                        .insert(host.to_lowercase(), (PathBuf::from(cert), key));
+                }
+                "--cert" => options.cert = Some(PathBuf::from(Self::value(&arg, args.next())?)),
+                "--key" => options.key = Some(PathBuf::from(Self::value(&arg, args.next())?)),
+                "--cacert" => options
+                    .cacerts
+                    .push(PathBuf::from(Self::value(&arg, args.next())?)),
+                "--tls-min" => {
+                    options.tls_min = Some(match Self::value(&arg, args.next())?.as_str() {
+                        "1.0" => Protocol::Tlsv10,
+                        "1.1" => Protocol::Tlsv11,
+                        "1.2" => Protocol::Tlsv12,
+                        _ => return Err(tr_with("expected-tls-version", &[("option", &arg)])),
+                    })
+                }
+                "--cert-password" => options.cert_password = Some(Self::value(&arg, args.next())?),
+                "-X" | "--request" => {
+                    options.method = Some(Self::value(&arg, args.next())?.to_uppercase())
+                }
+                "-d" | "--data" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    // `@file` sends the file, with line breaks left out as a form value cannot hold them
+                    let value = match value.strip_prefix('@') {
+                        Some(path) => fs::read_to_string(path)
+                            .map_err(|err| {
+                                tr_with(
+                                    "unreadable-file",
+                                    &[("path", path), ("error", &err.to_string())],
+                                )
+                            })?
+                            .replace(['\r', '\n'], ""),
+                        None => value,
+                    };
+
+                    options.data.push(value);
+                }
+                "--data-raw" => options.data.push(Self::value(&arg, args.next())?),
+                "--json" => {
+                    options.data.push(Self::value(&arg, args.next())?);
+                    options.json = true;
+                }
+                "-F" | "--form" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((name, value)) = value.split_once('=') else {
+                        return Err(tr_with("expected-form", &[("option", &arg)]));
+                    };
+
+                    let part = match value.strip_prefix('@') {
+                        Some(file) => {
+                            let (path, content_type) = match file.split_once(";type=") {
+                                Some((path, content_type)) => (path, Some(content_type)),
+                                None => (file, None),
+                            };
+
+                            Part::file(name, path, content_type).map_err(|err| {
+                                tr_with(
+                                    "unreadable-file",
+                                    &[("path", path), ("error", &err.to_string())],
+                                )
+                            })?
+                        }
+                        None => Part::text(name, value),
+                    };
+
+                    options.form.push(part);
+                }
+                "-H" | "--header" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((name, value)) = value
+                        .split_once(':')
+                        .filter(|(name, _)| !name.trim().is_empty())
+                    else {
+                        return Err(tr_with("expected-header", &[("option", &arg)]));
+                    };
+
+                    options
+                        .headers
+                        .push((name.trim().to_owned(), value.trim().to_owned()));
+                }
+                "--bearer" => options.bearer = Some(Self::value(&arg, args.next())?),
+                "--unix-socket" => {
+                    options.unix_socket = Some(PathBuf::from(Self::value(&arg, args.next())?))
+                }
+                "--connect-to" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some((origin, target)) = value.split_once('=') else {
+                        return Err(tr_with("expected-connect-to", &[("option", &arg)]));
+                    };
+
+                    options
+                        .connect_to
+                        .insert(origin.to_lowercase(), target.to_owned());
+                }
+                "--trace-dir" => {
+                    options.trace_dir = Some(PathBuf::from(Self::value(&arg, args.next())?))
+                }
+                "--throttle" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some(rate) = throttle::parse_rate(&value) else {
+                        return Err(tr_with("expected-rate", &[("option", &arg)]));
+                    };
+
+                    options.throttle = Some(rate);
+                }
+                "--latency" => {
+                    let value = Self::value(&arg, args.next())?;
+
+                    let Some(latency) = throttle::parse_duration(&value) else {
+                        return Err(tr_with("expected-duration", &[("option", &arg)]));
+                    };
+
+                    options.latency = Some(latency);
+                }
+                "--doh" => options.doh = Some(Self::value(&arg, args.next())?),
+                "--proxy" => options.proxy = Some(Self::value(&arg, args.next())?),
+                "--lang" => options.lang = Some(Self::value(&arg, args.next())?),
+                "--translate" => options.translate = Some(Self::value(&arg, args.next())?),
+                "--translate-to" => options.translate_to = Some(Self::value(&arg, args.next())?),
+                "--charset" => {
+                    let label = Self::value(&arg, args.next())?;
+
+                    let Some(charset) = Charset::from_label(&label) else {
+                        return Err(tr_with("unsupported-charset", &[("charset", &label)]));
+                    };
+
+                    options.charset = Some(charset);
+                }
+                "--home" => options.home = Some(Self::value(&arg, args.next())?),
+                "--complete" => options.complete = Some(Self::value(&arg, args.next())?),
+                "--max-redirects" => options.max_redirects = Some(Self::number(&arg, args.next())?),
+                "--retries" => options.retries = Some(Self::number(&arg, args.next())?),
+                "--connect-timeout" => {
+                    options.connect_timeout = Some(Self::number(&arg, args.next())?)
+                }
+                "--read-timeout" => options.read_timeout = Some(Self::number(&arg, args.next())?),
+                "--timeout" => options.timeout = Some(Self::number(&arg, args.next())?),
+                flag if flag.starts_with("--") => {
+                    return Err(tr_with("unknown-option", &[("option", flag)]))
+                }
+                _ => {
+                    if options.url.is_some() {
+                        return Err(tr_with("unexpected-argument", &[("argument", &arg)]));
+                    }
+
+                    options.url = Some(arg);
+                }
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+        value.ok_or_else(|| tr_with("missing-value", &[("option", flag)]))
+    }
+
+    fn number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+        Self::value(flag, value)?
+            .parse::<T>()
+            .map_err(|_| tr_with("expected-number", &[("option", flag)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_flags() {
+        let options = parse(&["--edit", "example.org"]).unwrap();
+
+        assert!(options.edit);
+        assert!(!options.toc);
+        assert!(!options.external);
+        assert_eq!(options.url.as_deref(), Some("example.org"));
+
+        let options = parse(&["--handler", "magnet=transmission-gtk", "magnet:?xt=1"]).unwrap();
+
+        assert_eq!(
+            options.handlers.get("magnet").map(String::as_str),
+            Some("transmission-gtk")
+        );
+
+        let options = parse(&["--max-redirects", "3", "--http2"]).unwrap();
+
+        assert_eq!(options.max_redirects, Some(3));
+        assert!(options.http2);
+        assert!(parse(&["--http1.0"]).unwrap().http1_0);
+
+        let options = parse(&["--show-cert", "--insecure", "example.org"]).unwrap();
+
+        assert!(options.show_cert && options.insecure);
+        assert!(!options.no_alt_svc);
+
+        let options = parse(&["--timeout", "0", "--read-timeout", "5"]).unwrap();
+
+        assert_eq!(options.timeout, Some(0));
+        assert_eq!(options.read_timeout, Some(5));
+        assert_eq!(options.connect_timeout, None);
+
+        let options = parse(&["--throttle", "256kbps", "--latency", "200ms"]).unwrap();
+
+        assert_eq!(options.throttle, Some(32_000));
+        assert_eq!(options.latency, Some(Duration::from_millis(200)));
+        assert!(parse(&["--throttle", "fast"]).is_err());
+
+        let options = parse(&["-H", "X-Debug: 1", "--header", "User-Agent:"]).unwrap();
+
+        assert_eq!(
+            options.headers,
+            [
+                ("X-Debug".to_string(), "1".to_string()),
+                ("User-Agent".to_string(), String::new())
+            ]
+        );
+        assert!(parse(&["-H", "no colon"]).is_err());
+
+        let options = parse(&["-d", "a=b", "--data-raw", "@c=d"]).unwrap();
+
+        assert_eq!(options.data, ["a=b", "@c=d"]);
+        assert!(!options.json);
+        assert!(parse(&["--data", "@/nonexistent/form.txt"]).is_err());
+
+        let options = parse(&["-X", "head", "example.org"]).unwrap();
+
+        assert_eq!(options.method.as_deref(), Some("HEAD"));
+
+        let options = parse(&["-o", "page.html", "example.org"]).unwrap();
+
+        assert_eq!(options.output, Some(PathBuf::from("page.html")));
+        assert!(!options.remote_name);
+        assert!(parse(&["--remote-name"]).unwrap().remote_name);
+        assert!(parse(&["-o"]).is_err());
+
+        let options = parse(&["--json", "{\"a\":1}"]).unwrap();
+
+        assert!(options.json);
+
+        let options = parse(&[
+            "-F",
+            "title=Hi",
+            "--form",
+            "src=@Cargo.toml;type=text/x-toml",
+        ])
+        .unwrap();
+
+        assert_eq!(options.form[0], Part::text("title", "Hi"));
+        assert_eq!(options.form[1].filename.as_deref(), Some("Cargo.toml"));
+        assert_eq!(options.form[1].content_type.as_deref(), Some("text/x-toml"));
+        assert!(parse(&["--form", "title"]).is_err());
+
+        assert_eq!(parse(&["--retries", "0"]).unwrap().retries, Some(0));
+        assert_eq!(
+            parse(&["--home", "blank"]).unwrap().home.as_deref(),
+            Some("blank")
+        );
+        assert_eq!(
+            parse(&["--complete", "rust"]).unwrap().complete.as_deref(),
+            Some("rust")
+        );
+
+        let options = parse(&["--connect-to", "Example.org:443=127.0.0.1:8443"]).unwrap();
+
+        assert_eq!(options.connect_to["example.org:443"], "127.0.0.1:8443");
+        assert!(parse(&["--connect-to", "example.org"]).is_err());
+
+        let options = parse(&["--tls-min", "1.2", "--cacert", "corp.pem"]).unwrap();
+
+        assert!(matches!(options.tls_min, Some(Protocol::Tlsv12)));
+        assert_eq!(options.cacerts, [PathBuf::from("corp.pem")]);
+        assert!(parse(&["--tls-min", "1.3"]).is_err());
+
+        assert_eq!(
+            parse(&["--charset", "Latin1"]).unwrap().charset,
+            Some(Charset::Windows1252)
+        );
+        assert!(parse(&["--charset", "shift_jis"]).is_err());
+
+        let options = parse(&["--translate", "trans -b", "--translate-to", "sv"]).unwrap();
+
+        assert_eq!(options.translate.as_deref(), Some("trans -b"));
+        assert_eq!(options.translate_to.as_deref(), Some("sv"));
+
+        let options = parse(&["--doh", "https://cloudflare-dns.com/dns-query"]).unwrap();
+
+        assert_eq!(
+            options.doh.as_deref(),
+            Some("https://cloudflare-dns.com/dns-query")
+        );
+
+        let options = parse(&["--client-cert", "Intranet.example=me.pem,me.key"]).unwrap();
+
+        assert_eq!(
+            options.client_certs["intranet.example"],
+            (PathBuf::from("me.pem"), Some(PathBuf::from("me.key")))
+        );
+        assert!(!options.no_persist_cookies);
+
+        assert!(parse(&["--max-redirects", "many"]).is_err());
+        assert!(parse(&["--handler"]).is_err());
+        assert!(parse(&["--handler", "magnet"]).is_err());
+        assert!(parse(&["--user", "ada"]).is_err());
+        assert!(parse(&["--nope"]).is_err());
+        assert!(parse(&["example.org", "example.com"]).is_err());
+    }
+}
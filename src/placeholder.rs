@@ -0,0 +1,105 @@
+use crate::entity::EntityParser;
+
+/// Text standing in for a tag that carries meaning outside its content,
+/// e.g. `<img alt="A cat">` becomes `[image: A cat]`
+pub fn for_tag(tag: &str, entity_parser: &EntityParser) -> Option<String> {
+    let name = tag
+        .split(|ch: char| ch.is_whitespace() || ch == '/' && !tag.starts_with('/'))
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let attribute = |name| attribute(tag, name).map(|value| entity_parser.decode(&value));
+
+    match name.as_str() {
+        // An empty alt marks the image as decorative
+        "img" => match attribute("alt").or_else(|| attribute("title")) {
+            Some(alt) if alt.trim().is_empty() => None,
+            Some(alt) => Some(format!("[image: {}]", alt.trim())),
+            None => Some("[image]".to_string()),
+        },
+        "figcaption" => Some("[caption: ".to_string()),
+        "/figcaption" => Some("]".to_string()),
+        _ => attribute("title")
+            .filter(|title| !title.trim().is_empty())
+            .map(|title| format!("[title: {}] ", title.trim())),
+    }
+}
+
+/// Finds an attribute value, quoted or not, in the text between `<` and `>`
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag.split_once(char::is_whitespace)?.1;
+
+    loop {
+        rest = rest.trim_start_matches(|ch: char| ch.is_whitespace() || ch == '/');
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = rest
+            .find(|ch: char| ch.is_whitespace() || ch == '=')
+            .unwrap_or(rest.len());
+
+        let key = &rest[..end];
+
+        rest = rest[end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        after[1..].split_once(quote).unwrap_or((&after[1..], ""))
+                    }
+                    _ => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+                };
+
+                rest = remaining;
+
+                value
+            }
+            None => "",
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_tags() {
+        let entity_parser = EntityParser::new();
+
+        let describe = |tag| for_tag(tag, &entity_parser);
+
+        assert_eq!(
+            describe("img src=cat.png alt=\"A cat &amp; a dog\"").as_deref(),
+            Some("[image: A cat & a dog]")
+        );
+        assert_eq!(
+            describe("IMG alt='logo'/").as_deref(),
+            Some("[image: logo]")
+        );
+        assert_eq!(
+            describe("img src=x.png alt=spacer").as_deref(),
+            Some("[image: spacer]")
+        );
+        assert_eq!(describe("img src=x.png alt=\"\"").as_deref(), None);
+        assert_eq!(describe("img src=x.png").as_deref(), Some("[image]"));
+        assert_eq!(describe("figcaption").as_deref(), Some("[caption: "));
+        assert_eq!(describe("/figcaption").as_deref(), Some("]"));
+        assert_eq!(
+            describe("abbr title=\"HyperText Markup Language\"").as_deref(),
+            Some("[title: HyperText Markup Language] ")
+        );
+        assert_eq!(describe("p class=\"title\"").as_deref(), None);
+        assert_eq!(describe("br/").as_deref(), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 6;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Idle<T> {
+    connection: T,
+    since: Instant,
+}
+
+/// Idle keep-alive connections, grouped by origin
+pub struct Pool<T> {
+    idle: HashMap<String, Vec<Idle<T>>>,
+    max_idle_per_host: usize,
+    idle_timeout: Duration,
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool {
+            idle: HashMap::new(),
+            max_idle_per_host: DEFAULT_MAX_IDLE_PER_HOST,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+impl<T> Pool<T> {
+    /// Hands out the most recently used idle connection for the origin, if any
+    pub fn checkout(&mut self, key: &str) -> Option<T> {
+        self.evict_stale();
+
+        let idle = self.idle.get_mut(key)?;
+
+        let connection = idle.pop().map(|idle| idle.connection);
+
+        if idle.is_empty() {
+            self.idle.remove(key);
+        }
+
+        connection
+    }
+
+    /// Returns a connection once its response has been fully read
+    pub fn checkin(&mut self, key: String, connection: T) {
+        let idle = self.idle.entry(key).or_default();
+
+        idle.push(Idle {
+            connection,
+            since: Instant::now(),
+        });
+
+        // Drop the least recently used sockets beyond the per host limit
+        if idle.len() > self.max_idle_per_host {
+            let excess = idle.len() - self.max_idle_per_host;
+
+            idle.drain(..excess);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.idle.values().map(Vec::len).sum()
+    }
+
+    fn evict_stale(&mut self) {
+        let timeout = self.idle_timeout;
+
+        self.idle.retain(|_, idle| {
+            idle.retain(|idle| idle.since.elapsed() < timeout);
+
+            !idle.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkout_most_recent() {
+        let mut pool = Pool::default();
+
+        pool.checkin("http://example.org:80".to_string(), 1);
+        pool.checkin("http://example.org:80".to_string(), 2);
+
+        assert_eq!(pool.checkout("http://example.org:80"), Some(2));
+        assert_eq!(pool.checkout("http://example.org:80"), Some(1));
+        assert_eq!(pool.checkout("http://example.org:80"), None);
+        assert_eq!(pool.checkout("http://example.com:80"), None);
+    }
+
+    #[test]
+    fn limit_idle_per_host() {
+        let mut pool = Pool {
+            max_idle_per_host: 2,
+            ..Pool::default()
+        };
+
+        for connection in 0..5 {
+            pool.checkin("http://example.org:80".to_string(), connection);
+        }
+
+        pool.checkin("http://example.com:80".to_string(), 9);
+
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.checkout("http://example.org:80"), Some(4));
+        assert_eq!(pool.checkout("http://example.org:80"), Some(3));
+        assert_eq!(pool.checkout("http://example.org:80"), None);
+    }
+
+    #[test]
+    fn evict_stale_connections() {
+        let mut pool = Pool {
+            idle_timeout: Duration::ZERO,
+            ..Pool::default()
+        };
+
+        pool.checkin("http://example.org:80".to_string(), 1);
+
+        assert_eq!(pool.checkout("http://example.org:80"), None);
+        assert_eq!(pool.len(), 0);
+    }
+}
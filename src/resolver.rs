@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::connection::{Connection, Settings};
+use crate::i18n::tr_with;
+use crate::{Request, Url};
+
+/// The system resolver does not tell how long an answer holds, so keep it this long
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+/// Head start for each address before the next one is tried alongside, as in RFC 8305
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+const DNS_TYPE_A: u16 = 1;
+const DNS_TYPE_AAAA: u16 = 28;
+
+struct Cached {
+    addrs: Vec<SocketAddr>,
+    expires: Instant,
+}
+
+/// Time spent resolving, for `--stats`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Timing {
+    pub lookups: usize,
+    /// Lookups answered from the cache, which take no time
+    pub cached: usize,
+    pub elapsed: Duration,
+}
+
+impl Timing {
+    pub fn report(&self) -> String {
+        tr_with(
+            "stats-dns",
+            &[
+                ("milliseconds", &self.elapsed.as_millis().to_string()),
+                ("lookups", &self.lookups.to_string()),
+                ("cached", &self.cached.to_string()),
+            ],
+        )
+    }
+}
+
+/// Turns `hostname:port` into addresses, remembering answers for a while
+pub struct Resolver {
+    /// DNS-over-HTTPS endpoint asked instead of the system, e.g. `https://cloudflare-dns.com/dns-query`
+    doh: Option<String>,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, Cached>>,
+    timing: Mutex<Timing>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new(None)
+    }
+}
+
+impl Resolver {
+    pub fn new(doh: Option<String>) -> Self {
+        Resolver {
+            doh,
+            ttl: DEFAULT_TTL,
+            cache: Mutex::new(HashMap::new()),
+            timing: Mutex::new(Timing::default()),
+        }
+    }
+
+    /// Every address for `host`, e.g. `example.org:443`, with IPv6 and IPv4 taking turns
+    ///
+    /// A DoH server is reached with the timeouts, trusted roots and proxy of `settings`.
+    pub fn lookup(&self, host: &str, settings: &Settings) -> io::Result<Vec<SocketAddr>> {
+        let key = host.to_lowercase();
+
+        let now = Instant::now();
+
+        if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+            if cached.expires > now {
+                self.record(true, Duration::ZERO);
+
+                return Ok(cached.addrs.clone());
+            }
+        }
+
+        let (addrs, ttl) = match &self.doh {
+            // Addresses need no resolving, and the DoH server itself is found through the system
+            Some(doh) if host.parse::<SocketAddr>().is_err() => {
+                let (addrs, ttl) = query_doh(doh, host, settings)?;
+
+                (addrs, ttl.unwrap_or(self.ttl))
+            }
+            _ => (host.to_socket_addrs()?.collect(), self.ttl),
+        };
+
+        let addrs = interleave(addrs);
+
+        self.record(false, now.elapsed());
+
+        if addrs.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        self.cache.lock().unwrap().insert(
+            key,
+            Cached {
+                addrs: addrs.clone(),
+                expires: now + ttl,
+            },
+        );
+
+        Ok(addrs)
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing.lock().unwrap().clone()
+    }
+
+    fn record(&self, cached: bool, elapsed: Duration) {
+        let mut timing = self.timing.lock().unwrap();
+
+        timing.lookups += 1;
+        timing.elapsed += elapsed;
+
+        if cached {
+            timing.cached += 1;
+        }
+    }
+}
+
+/// Asks a DoH server for the A and AAAA records of `host`, through its JSON API
+fn query_doh(
+    doh: &str,
+    host: &str,
+    settings: &Settings,
+) -> io::Result<(Vec<SocketAddr>, Option<Duration>)> {
+    let Some((hostname, port)) = host
+        .rsplit_once(':')
+        .and_then(|(hostname, port)| Some((hostname, port.parse::<u16>().ok()?)))
+    else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+
+    let separator = if doh.contains('?') { '&' } else { '?' };
+
+    // The user's network setup, with the DoH server itself found through the system
+    let settings = Settings {
+        proxy: settings.proxy.clone(),
+        connect_timeout: settings.connect_timeout,
+        read_timeout: settings.read_timeout,
+        extra_roots: settings.extra_roots.clone(),
+        min_tls_version: settings.min_tls_version,
+        insecure: settings.insecure,
+        ..Settings::default()
+    };
+
+    let mut connection = None;
+
+    let mut addrs = vec![];
+
+    let mut ttl: Option<Duration> = None;
+
+    for record in [DNS_TYPE_AAAA, DNS_TYPE_A] {
+        let url = Url::new(&format!("{doh}{separator}name={hostname}&type={record}"))
+            .map_err(io::Error::other)?;
+
+        let mut request = Request::new(&url, "GET");
+
+        request.header("Accept", "application/dns-json");
+
+        let mut open = match connection.take() {
+            Some(open) => open,
+            None => Connection::open(&url, &settings).map_err(io::Error::other)?,
+        };
+
+        let (response, reusable) = open
+            .execute(&request)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+
+        if response.status_code != 200 {
+            return Err(io::Error::other(format!(
+                "{} {}",
+                response.status_code, response.explanation
+            )));
+        }
+
+        let (found, shortest) = parse_dns_json(&response.text())?;
+
+        addrs.extend(
+            found
+                .into_iter()
+                .map(|address| SocketAddr::new(address, port)),
+        );
+
+        ttl = match (ttl, shortest) {
+            (Some(ttl), Some(shortest)) => Some(ttl.min(shortest)),
+            (ttl, shortest) => ttl.or(shortest),
+        };
+
+        if reusable {
+            connection = Some(open);
+        }
+    }
+
+    Ok((addrs, ttl))
+}
+
+/// The value after `"name":` in a flat JSON object, unquoted
+fn json_field<'a>(object: &'a str, name: &str) -> Option<&'a str> {
+    let start = object.find(&format!("\"{name}\""))? + name.len() + 2;
+
+    let value = object[start..].trim_start().strip_prefix(':')?.trim_start();
+
+    let end = match value.strip_prefix('"') {
+        Some(quoted) => return quoted.split('"').next(),
+        None => value.find([',', '}', ']']).unwrap_or(value.len()),
+    };
+
+    Some(value[..end].trim())
+}
+
+/// Addresses and their shortest TTL from an `application/dns-json` answer, skipping CNAMEs
+fn parse_dns_json(body: &str) -> io::Result<(Vec<IpAddr>, Option<Duration>)> {
+    // Anything but NOERROR, e.g. 3 for NXDOMAIN, means there is nothing to connect to
+    if json_field(body, "Status") != Some("0") {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+
+    let answers = body
+        .find("\"Answer\"")
+        .and_then(|start| {
+            let rest = &body[start..];
+
+            Some(&rest[rest.find('[')? + 1..rest.find(']')?])
+        })
+        .unwrap_or_default();
+
+    let mut addrs = vec![];
+
+    let mut ttl: Option<Duration> = None;
+
+    for answer in answers.split('}') {
+        let record = json_field(answer, "type").and_then(|record| record.parse::<u16>().ok());
+
+        if !matches!(record, Some(DNS_TYPE_A | DNS_TYPE_AAAA)) {
+            continue;
+        }
+
+        let Some(address) = json_field(answer, "data").and_then(|data| data.parse().ok()) else {
+            continue;
+        };
+
+        addrs.push(address);
+
+        if let Some(seconds) = json_field(answer, "TTL").and_then(|ttl| ttl.parse().ok()) {
+            let seconds = Duration::from_secs(seconds);
+
+            ttl = Some(ttl.map_or(seconds, |ttl| ttl.min(seconds)));
+        }
+    }
+
+    Ok((addrs, ttl))
+}
+
+/// Alternates address families, IPv6 first, so a broken one costs a single attempt
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let (mut v6, mut v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+
+    v6.reverse();
+    v4.reverse();
+
+    let mut interleaved = vec![];
+
+    while let Some(addr) = v6.pop().or_else(|| v4.pop()) {
+        interleaved.push(addr);
+
+        if let Some(addr) = v4.pop() {
+            interleaved.push(addr);
+        }
+    }
+
+    interleaved
+}
+
+fn attempt(addr: SocketAddr, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    match timeout {
+        Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+        None => TcpStream::connect(addr),
+    }
+}
+
+/// Happy Eyeballs: each address gets a head start, and the first to connect wins
+pub fn connect(addrs: &[SocketAddr], timeout: Option<Duration>) -> io::Result<TcpStream> {
+    if let [addr] = addrs {
+        return attempt(*addr, timeout);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+
+    let mut addrs = addrs.iter().copied().peekable();
+
+    let mut pending = 0;
+
+    let mut last_error = io::Error::from(io::ErrorKind::NotFound);
+
+    loop {
+        if let Some(addr) = addrs.next() {
+            let sender = sender.clone();
+
+            // A losing attempt finishes on its own, its socket dropped with the message
+            thread::spawn(move || {
+                let _ = sender.send(attempt(addr, timeout));
+            });
+
+            pending += 1;
+        }
+
+        if pending == 0 {
+            return Err(last_error);
+        }
+
+        let outcome = if addrs.peek().is_some() {
+            match receiver.recv_timeout(ATTEMPT_DELAY) {
+                Ok(outcome) => outcome,
+                Err(_) => continue,
+            }
+        } else {
+            receiver
+                .recv()
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?
+        };
+
+        match outcome {
+            Ok(socket) => return Ok(socket),
+            Err(err) => {
+                pending -= 1;
+                last_error = err;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn alternate_address_families() {
+        let addrs = [
+            "[::1]:80",
+            "[::2]:80",
+            "127.0.0.1:80",
+            "127.0.0.2:80",
+            "127.0.0.3:80",
+        ]
+        .map(|addr| addr.parse::<SocketAddr>().unwrap());
+
+        let interleaved = interleave(addrs.to_vec());
+
+        assert_eq!(
+            interleaved,
+            [addrs[0], addrs[2], addrs[1], addrs[3], addrs[4]]
+        );
+    }
+
+    #[test]
+    fn cache_lookups() {
+        let resolver = Resolver::default();
+
+        let settings = Settings::default();
+
+        let addrs = resolver.lookup("127.0.0.1:8080", &settings).unwrap();
+
+        assert_eq!(addrs, ["127.0.0.1:8080".parse().unwrap()]);
+        assert_eq!(resolver.lookup("127.0.0.1:8080", &settings).unwrap(), addrs);
+
+        let timing = resolver.timing();
+
+        assert_eq!((timing.lookups, timing.cached), (2, 1));
+    }
+
+    #[test]
+    fn resolve_over_https() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut requests = vec![];
+
+            let answers = [
+                r#"{"Status":0,"Answer":[]}"#,
+                r#"{"Status": 0, "Answer": [{"name": "www.example.org", "type": 5, "TTL": 300, "data": "example.org."}, {"name": "example.org", "type": 1, "TTL": 120, "data": "93.184.215.14"}]}"#,
+            ];
+
+            for answer in answers {
+                let mut head = String::new();
+
+                while reader
+                    .read_line(&mut head)
+                    .is_ok_and(|read| read > 0 && !head.ends_with("\r\n\r\n"))
+                {}
+
+                requests.push(head);
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/dns-json\r\nContent-Length: {}\r\n\r\n{answer}",
+                    answer.len()
+                );
+
+                (&stream).write_all(response.as_bytes()).unwrap();
+            }
+
+            requests
+        });
+
+        let resolver = Resolver::new(Some("http://doh.invalid/dns-query".to_string()));
+
+        // The DoH server is only reachable through the proxy the user set
+        let settings = Settings {
+            proxy: Some(format!("http://{addr}")),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            resolver.lookup("www.example.org:443", &settings).unwrap(),
+            ["93.184.215.14:443".parse().unwrap()]
+        );
+
+        let requests = server.join().unwrap();
+
+        assert!(requests[0]
+            .starts_with("GET http://doh.invalid:80/dns-query?name=www.example.org&type=28 "));
+        assert!(requests[1]
+            .starts_with("GET http://doh.invalid:80/dns-query?name=www.example.org&type=1 "));
+        assert!(requests[1].contains("\r\nAccept: application/dns-json\r\n"));
+
+        // Literal addresses never reach the server, which is gone by now
+        assert!(resolver.lookup("127.0.0.1:80", &settings).is_ok());
+
+        assert!(parse_dns_json(r#"{"Status":3,"Answer":[]}"#).is_err());
+    }
+
+    #[test]
+    fn fall_back_to_the_next_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        // Nothing listens on a port just released
+        let refused = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let addrs = [refused, listener.local_addr().unwrap()];
+
+        let socket = connect(&addrs, Some(Duration::from_secs(1))).unwrap();
+
+        assert_eq!(socket.peer_addr().unwrap(), addrs[1]);
+        assert!(connect(&addrs[..1], Some(Duration::from_secs(1))).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::i18n::tr_with;
+use crate::placeholder::attribute;
+
+/// Average silent reading speed, in words per minute
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Counts describing a document, for writers and page weight audits
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub words: usize,
+    pub links: usize,
+    pub images: usize,
+    pub scripts: usize,
+    /// Scripts loaded from a `src`, whose weight is not known without fetching them
+    pub external_scripts: usize,
+    pub stylesheets: usize,
+    pub html_bytes: usize,
+    pub inline_script_bytes: usize,
+    pub inline_style_bytes: usize,
+}
+
+/// Moves past the content of `<script>` or `<style>`, which may contain `<`, returning its length
+fn skip_raw_text(rest: &mut &str, name: &str) -> usize {
+    let close = format!("</{name}");
+
+    let length = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+
+    let after = &rest[length..];
+
+    *rest = after.find('>').map_or("", |end| &after[end + 1..]);
+
+    length
+}
+
+impl Stats {
+    pub fn collect(body: &str) -> Self {
+        let mut stats = Stats {
+            html_bytes: body.len(),
+            ..Stats::default()
+        };
+
+        let mut text = String::new();
+
+        let mut rest = body;
+
+        while let Some(start) = rest.find('<') {
+            text.push_str(&rest[..start]);
+            text.push(' ');
+
+            let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+                rest = "";
+                break;
+            };
+
+            let tag = &rest[start + 1..end];
+
+            rest = &rest[end + 1..];
+
+            let name = tag
+                .split(|ch: char| ch.is_whitespace() || ch == '/' && !tag.starts_with('/'))
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+
+            match name.as_str() {
+                "a" if attribute(tag, "href").is_some() => stats.links += 1,
+                "img" => stats.images += 1,
+                "script" => {
+                    stats.scripts += 1;
+
+                    if attribute(tag, "src").is_some() {
+                        stats.external_scripts += 1;
+                    }
+
+                    stats.inline_script_bytes += skip_raw_text(&mut rest, "script");
+                }
+                "style" => stats.inline_style_bytes += skip_raw_text(&mut rest, "style"),
+                "link" => {
+                    let stylesheet = attribute(tag, "rel").is_some_and(|rel| {
+                        rel.split_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+                    });
+
+                    if stylesheet {
+                        stats.stylesheets += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        text.push_str(rest);
+
+        stats.words = text.split_whitespace().count();
+
+        stats
+    }
+
+    /// Rounded up, so that any text takes at least a minute
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+
+    pub fn report(&self) -> String {
+        let count = |key, count: usize| tr_with(key, &[("count", &count.to_string())]);
+
+        [
+            count("stats-words", self.words),
+            tr_with(
+                "stats-reading-time",
+                &[("minutes", &self.reading_minutes().to_string())],
+            ),
+            count("stats-links", self.links),
+            count("stats-images", self.images),
+            tr_with(
+                "stats-scripts",
+                &[
+                    ("count", &self.scripts.to_string()),
+                    ("external", &self.external_scripts.to_string()),
+                ],
+            ),
+            count("stats-stylesheets", self.stylesheets),
+            tr_with(
+                "stats-weight",
+                &[
+                    ("html", &self.html_bytes.to_string()),
+                    ("scripts", &self.inline_script_bytes.to_string()),
+                    ("styles", &self.inline_style_bytes.to_string()),
+                ],
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_stats() {
+        let body = "<html><head><link rel=\"preload stylesheet\" href=a.css>\
+            <style>p { color: red }</style><script src=app.js></script></head>\
+            <body><p>One two <a href=/three>three</a></p><a name=anchor>four</a>\
+            <img src=cat.png alt=cat><script>if (a < b) { five() }</script></body></html>";
+
+        let stats = Stats::collect(body);
+
+        assert_eq!(
+            stats,
+            Stats {
+                words: 4,
+                links: 1,
+                images: 1,
+                scripts: 2,
+                external_scripts: 1,
+                stylesheets: 1,
+                html_bytes: body.len(),
+                inline_script_bytes: "if (a < b) { five() }".len(),
+                inline_style_bytes: "p { color: red }".len(),
+            }
+        );
+
+        assert_eq!(stats.reading_minutes(), 1);
+        assert_eq!(Stats::collect("").reading_minutes(), 0);
+    }
+}
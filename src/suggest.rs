@@ -0,0 +1,66 @@
+const DEFAULT_TLD: &str = "com";
+
+const TLD_TYPOS: &[(&str, &str)] = &[
+    ("con", "com"),
+    ("cmo", "com"),
+    ("ocm", "com"),
+    ("vom", "com"),
+    ("comm", "com"),
+    ("og", "org"),
+    ("ogr", "org"),
+    ("orh", "org"),
+    ("nte", "net"),
+    ("ent", "net"),
+    ("nett", "net"),
+];
+
+/// Likely intended hostname for one that failed to resolve
+pub fn hostname(hostname: &str) -> Option<String> {
+    let mut labels = hostname.split('.').collect::<Vec<_>>();
+
+    if let Some(first) = labels.first_mut() {
+        if matches!(*first, "ww" | "wwww" | "ww2" | "wwe") {
+            *first = "www";
+        }
+    }
+
+    if let Some(tld) = labels.last_mut() {
+        if let Some((_, fixed)) = TLD_TYPOS.iter().find(|(typo, _)| typo == tld) {
+            *tld = fixed;
+        }
+    }
+
+    // `example` or `www.example` are missing their top level domain
+    let missing_tld = match labels[..] {
+        [name] => name != "localhost",
+        ["www", _] => true,
+        _ => false,
+    };
+
+    let mut candidate = labels.join(".");
+
+    if missing_tld {
+        candidate = format!("{candidate}.{DEFAULT_TLD}");
+    }
+
+    (candidate != hostname).then_some(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggest_hostname() {
+        let suggest = |name| hostname(name).unwrap_or_default();
+
+        assert_eq!(suggest("ww.example.com"), "www.example.com");
+        assert_eq!(suggest("example.con"), "example.com");
+        assert_eq!(suggest("wwww.example.ogr"), "www.example.org");
+        assert_eq!(suggest("example"), "example.com");
+        assert_eq!(suggest("www.example"), "www.example.com");
+
+        assert_eq!(hostname("localhost"), None);
+        assert_eq!(hostname("example.org"), None);
+    }
+}
@@ -0,0 +1,215 @@
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Network conditions to simulate, e.g. `--throttle 256kbps --latency 200ms` for a poor mobile link
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Throttle {
+    /// Bytes per second in each direction, unlimited when `None`
+    pub rate: Option<u64>,
+    /// Waited once per round trip: on connecting, and before the reply to what was sent
+    pub latency: Duration,
+}
+
+impl Throttle {
+    pub fn is_active(&self) -> bool {
+        self.rate.is_some() || !self.latency.is_zero()
+    }
+}
+
+/// Bytes per second from `256kbps`, `1.5Mbps` or `800bps` in bits, or `64KB/s` and `1MB/s` in bytes
+pub fn parse_rate(text: &str) -> Option<u64> {
+    let split = text
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(text.len());
+
+    let (number, unit) = text.split_at(split);
+
+    let number = number.parse::<f64>().ok()?;
+
+    let scale = match unit {
+        "B/s" | "" => 1.0,
+        "KB/s" => 1_000.0,
+        "MB/s" => 1_000_000.0,
+        _ => match unit.to_lowercase().as_str() {
+            "bps" => 1.0 / 8.0,
+            "kbps" => 1_000.0 / 8.0,
+            "mbps" => 1_000_000.0 / 8.0,
+            "gbps" => 1_000_000_000.0 / 8.0,
+            _ => return None,
+        },
+    };
+
+    let rate = (number * scale) as u64;
+
+    (rate > 0).then_some(rate)
+}
+
+/// `200ms` or `1.5s`; a bare number counts milliseconds
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    if let Some(millis) = text.strip_suffix("ms") {
+        return millis.parse::<u64>().ok().map(Duration::from_millis);
+    }
+
+    if let Some(seconds) = text.strip_suffix('s') {
+        return Duration::try_from_secs_f64(seconds.parse::<f64>().ok()?).ok();
+    }
+
+    text.parse::<u64>().ok().map(Duration::from_millis)
+}
+
+/// Bytes moved in one direction since `since`, to hold the pace to the rate
+#[derive(Debug)]
+struct Pace {
+    since: Instant,
+    bytes: u64,
+}
+
+impl Pace {
+    fn new() -> Self {
+        Pace {
+            since: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Counts `bytes` more, sleeping until they would have taken that long at `rate`
+    fn consume(&mut self, bytes: usize, rate: u64) {
+        self.bytes += bytes as u64;
+
+        let due = Duration::from_secs_f64(self.bytes as f64 / rate as f64);
+
+        if let Some(wait) = due.checked_sub(self.since.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// A transport slowed down to the bandwidth and latency of a `Throttle`
+#[derive(Debug)]
+pub struct Shaped<S> {
+    inner: S,
+    throttle: Throttle,
+    read: Pace,
+    written: Pace,
+    /// Whether something was sent that the next read answers, costing a round trip
+    awaiting_reply: bool,
+}
+
+impl<S> Shaped<S> {
+    /// Wraps a freshly connected transport, whose handshake already took a round trip
+    pub fn new(inner: S, throttle: Throttle) -> Self {
+        thread::sleep(throttle.latency);
+
+        Shaped {
+            inner,
+            throttle,
+            read: Pace::new(),
+            written: Pace::new(),
+            awaiting_reply: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Caps a transfer to a twentieth of a second's worth, keeping the pace smooth
+    fn cap(&self, len: usize) -> usize {
+        match self.throttle.rate {
+            Some(rate) => len.min((rate as usize / 20).max(1)),
+            None => len,
+        }
+    }
+}
+
+impl<S: Read> Read for Shaped<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.awaiting_reply {
+            self.awaiting_reply = false;
+
+            thread::sleep(self.throttle.latency);
+
+            // Time spent idle or sending does not count towards the download rate
+            self.read = Pace::new();
+        }
+
+        let len = self.cap(buf.len());
+
+        let read = self.inner.read(&mut buf[..len])?;
+
+        if let Some(rate) = self.throttle.rate {
+            self.read.consume(read, rate);
+        }
+
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Shaped<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.awaiting_reply {
+            self.awaiting_reply = true;
+
+            self.written = Pace::new();
+        }
+
+        let len = self.cap(buf.len());
+
+        let written = self.inner.write(&buf[..len])?;
+
+        if let Some(rate) = self.throttle.rate {
+            self.written.consume(written, rate);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_conditions() {
+        assert_eq!(parse_rate("256kbps"), Some(32_000));
+        assert_eq!(parse_rate("1.5Mbps"), Some(187_500));
+        assert_eq!(parse_rate("64KB/s"), Some(64_000));
+        assert_eq!(parse_rate("2048"), Some(2048));
+        assert_eq!(parse_rate("fast"), None);
+        assert_eq!(parse_rate("0kbps"), None);
+
+        assert_eq!(parse_duration("200ms"), Some(Duration::from_millis(200)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("75"), Some(Duration::from_millis(75)));
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn pace_transfers() {
+        let throttle = Throttle {
+            rate: Some(4000),
+            latency: Duration::from_millis(50),
+        };
+
+        let mut shaped = Shaped::new(Cursor::new(vec![7; 800]), throttle);
+
+        let started = Instant::now();
+
+        // Overwrites the start of the cursor, leaving the rest to read
+        shaped.write_all(b"GET").unwrap();
+
+        let mut body = vec![];
+
+        shaped.read_to_end(&mut body).unwrap();
+
+        // One round trip, then 797 bytes at 4000 bytes a second
+        assert!(started.elapsed() >= Duration::from_millis(240));
+        assert_eq!(body.len(), 797);
+    }
+}
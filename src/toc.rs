@@ -0,0 +1,143 @@
+use crate::entity::EntityParser;
+
+#[derive(Debug, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+}
+
+/// Every `h1`–`h6` in document order, with markup stripped from the text
+pub fn headings(body: &str, entity_parser: &EntityParser) -> Vec<Heading> {
+    let lower = body.to_ascii_lowercase();
+
+    let mut headings = vec![];
+
+    let mut rest = 0;
+
+    while let Some(start) = lower[rest..].find("<h").map(|start| rest + start) {
+        rest = start + 2;
+
+        let Some(level) = lower[rest..]
+            .chars()
+            .next()
+            .and_then(|level| level.to_digit(10))
+            .filter(|level| (1..=6).contains(level))
+        else {
+            continue;
+        };
+
+        // Skip `<hr>`, `<header>` and friends, which only share the prefix
+        if !lower[rest + 1..].starts_with(|ch: char| ch == '>' || ch.is_whitespace()) {
+            continue;
+        }
+
+        let Some(open_end) = lower[rest..].find('>').map(|end| rest + end + 1) else {
+            break;
+        };
+
+        let close = format!("</h{level}");
+
+        let end = lower[open_end..]
+            .find(&close)
+            .map_or(lower.len(), |end| open_end + end);
+
+        let text = strip_tags(&body[open_end..end]);
+
+        let text = entity_parser.decode(&text);
+
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if !text.is_empty() {
+            headings.push(Heading {
+                level: level as usize,
+                text,
+            });
+        }
+
+        rest = end;
+    }
+
+    headings
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+
+    let mut in_tag = false;
+
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+
+    text
+}
+
+/// An indented, numbered outline, e.g. `2.1 Installing` under `2 Usage`
+pub fn render(headings: &[Heading]) -> String {
+    let mut counters = [0usize; 6];
+
+    let mut outline = String::new();
+
+    let top = headings
+        .iter()
+        .map(|heading| heading.level)
+        .min()
+        .unwrap_or(1);
+
+    for heading in headings {
+        let depth = heading.level - top;
+
+        counters[depth] += 1;
+
+        for counter in &mut counters[depth + 1..] {
+            *counter = 0;
+        }
+
+        // Skipped levels, e.g. an h3 right under an h1, still get a number
+        let number = counters[..=depth]
+            .iter()
+            .map(|counter| counter.max(&1).to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        outline.push_str(&format!(
+            "{indent}{number} {text}\n",
+            indent = "  ".repeat(depth),
+            text = heading.text
+        ));
+    }
+
+    outline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outline_headings() {
+        let body = "<header><h1 id=top>Browser <em>Voy</em></h1></header><hr>\
+            <h2>Usage</h2><p>...</p><H3>Flags &amp; options</H3>\
+            <h2>\n  Building\n</h2><h4>Skipped</h4><h2></h2>";
+
+        let headings = headings(body, &EntityParser::new());
+
+        assert_eq!(
+            headings
+                .iter()
+                .map(|heading| heading.level)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 2, 4]
+        );
+
+        assert_eq!(
+            render(&headings),
+            "1 Browser Voy\n  1.1 Usage\n    1.1.1 Flags & options\n  1.2 Building\n      1.2.1.1 Skipped\n"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Writes every exchange to numbered files under `--trace-dir`, to replay protocol bugs offline
+///
+/// Exchange 7 leaves `0007-request.txt` as sent, `0007-response.txt` with the head and body as
+/// received, before content decoding, and `0007-body.txt` once the body could be decoded.
+#[derive(Debug)]
+pub struct Trace {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+/// The number a trace file starts with, e.g. 7 for `0007-request.txt`
+fn number(path: &Path) -> Option<usize> {
+    path.file_name()?.to_str()?.split_once('-')?.0.parse().ok()
+}
+
+impl Trace {
+    /// Numbering continues after the exchanges already in `dir`, so reruns add to it
+    pub fn new(dir: PathBuf) -> Self {
+        let last = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| number(&entry.ok()?.path()))
+            .max()
+            .unwrap_or(0);
+
+        Trace {
+            dir,
+            next: AtomicUsize::new(last + 1),
+        }
+    }
+
+    /// Writes the request and the raw response, returning the exchange number
+    pub fn record(&self, request: &[u8], head: &str, body: &[u8]) -> usize {
+        let number = self.next.fetch_add(1, Ordering::Relaxed);
+
+        let mut response = head.as_bytes().to_vec();
+
+        response.extend(b"\r\n");
+        response.extend(body);
+
+        self.write(number, "request", request);
+        self.write(number, "response", &response);
+
+        number
+    }
+
+    /// Writes the body of exchange `number` as it reads after content decoding
+    pub fn record_decoded(&self, number: usize, body: &[u8]) {
+        self.write(number, "body", body);
+    }
+
+    fn write(&self, number: usize, part: &str, bytes: &[u8]) {
+        // A trace that cannot be written should not cost the page itself
+        let _ = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(self.dir.join(format!("{number:04}-{part}.txt")), bytes));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn number_exchanges() {
+        let dir = env::temp_dir().join(format!("voy-trace-{}", std::process::id()));
+
+        let trace = Trace::new(dir.clone());
+
+        let first = trace.record(
+            b"GET / HTTP/1.1\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n",
+            b"\x1f\x8b",
+        );
+
+        trace.record_decoded(first, b"Hello");
+
+        // A later run picks up where the last one stopped
+        let second = Trace::new(dir.clone()).record(b"GET /next HTTP/1.1\r\n\r\n", "", b"");
+
+        let response = fs::read(dir.join("0001-response.txt")).unwrap();
+        let body = fs::read_to_string(dir.join("0001-body.txt")).unwrap();
+
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(response, b"HTTP/1.1 200 OK\r\n\r\n\x1f\x8b");
+        assert_eq!(body, "Hello");
+    }
+}
@@ -0,0 +1,550 @@
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::auth::md5;
+use crate::base64;
+use crate::connection::Connection;
+use crate::i18n::{tr, tr_with};
+
+/// Appended to `Sec-WebSocket-Key` before hashing it into `Sec-WebSocket-Accept` (RFC 6455)
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Messages beyond this size are refused rather than exhausting memory
+const MAX_MESSAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How long the session waits on the server before looking for typed lines again
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a closing handshake may wait for the server to close too
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The status sent when the user ends the session
+const NORMAL_CLOSURE: u16 = 1000;
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// The status code and reason, when the closing side gave one
+    Close(Option<(u16, String)>),
+}
+
+/// One frame off the wire, unmasked
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Whether `url` is a `ws:` or `wss:` URL
+pub fn is_websocket(url: &str) -> bool {
+    url.split_once("://")
+        .is_some_and(|(scheme, _)| matches!(scheme.to_lowercase().as_str(), "ws" | "wss"))
+}
+
+/// The URL the opening handshake is sent to, `wss://example.org/` as `https://example.org/`
+pub fn http_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("ws", url));
+
+    match scheme.to_lowercase().as_str() {
+        "wss" => format!("https://{rest}"),
+        _ => format!("http://{rest}"),
+    }
+}
+
+/// Bytes no one can predict, for the handshake key and frame masks
+fn nonce() -> [u8; 16] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+
+    md5(format!("{nanos}:{}", std::process::id()).as_bytes())
+}
+
+/// A fresh `Sec-WebSocket-Key`, 16 bytes in base64
+pub fn key() -> String {
+    base64::encode(&nonce())
+}
+
+/// The `Sec-WebSocket-Accept` a server must answer `key` with
+pub fn accept(key: &str) -> String {
+    base64::encode(&sha1(format!("{key}{GUID}").as_bytes()))
+}
+
+/// Writes a message as a single frame, masked as every frame from a client must be
+pub fn send<W: Write>(writer: &mut W, message: &Message) -> io::Result<()> {
+    let (opcode, payload) = match message {
+        Message::Text(text) => (TEXT, text.as_bytes().to_vec()),
+        Message::Binary(bytes) => (BINARY, bytes.clone()),
+        Message::Ping(bytes) => (PING, bytes.clone()),
+        Message::Pong(bytes) => (PONG, bytes.clone()),
+        Message::Close(None) => (CLOSE, vec![]),
+        Message::Close(Some((code, reason))) => {
+            let mut payload = code.to_be_bytes().to_vec();
+
+            payload.extend(reason.as_bytes());
+
+            (CLOSE, payload)
+        }
+    };
+
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xffff => {
+            frame.push(0x80 | 126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+
+    let mask = &nonce()[..4];
+
+    frame.extend(mask);
+    frame.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| byte ^ mask[index % 4]),
+    );
+
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// Reads the next message, joining the frames of a fragmented one
+///
+/// Control frames may arrive between fragments and are returned as they come, the fragments
+/// read so far being lost; servers rarely fragment messages short enough for that to matter.
+pub fn receive<R: Read>(reader: &mut R) -> io::Result<Message> {
+    let mut frame = read_frame(reader)?;
+
+    let opcode = frame.opcode;
+
+    if opcode == TEXT || opcode == BINARY {
+        while !frame.fin {
+            let next = read_frame(reader)?;
+
+            if next.opcode != CONTINUATION {
+                return Err(invalid("Expected a continuation frame"));
+            }
+
+            if (frame.payload.len() + next.payload.len()) as u64 > MAX_MESSAGE_BYTES {
+                return Err(invalid("Message too large"));
+            }
+
+            frame.payload.extend(next.payload);
+            frame.fin = next.fin;
+        }
+    }
+
+    Ok(match opcode {
+        TEXT => Message::Text(
+            String::from_utf8(frame.payload).map_err(|_| invalid("Text is not UTF-8"))?,
+        ),
+        BINARY => Message::Binary(frame.payload),
+        PING => Message::Ping(frame.payload),
+        PONG => Message::Pong(frame.payload),
+        CLOSE => Message::Close(match &frame.payload[..] {
+            [high, low, reason @ ..] => Some((
+                u16::from_be_bytes([*high, *low]),
+                String::from_utf8_lossy(reason).into_owned(),
+            )),
+            _ => None,
+        }),
+        opcode => return Err(invalid(&format!("Unexpected opcode {opcode:#x}"))),
+    })
+}
+
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Frame> {
+    let mut head = [0; 2];
+
+    reader.read_exact(&mut head)?;
+
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut len = [0; 2];
+
+            reader.read_exact(&mut len)?;
+
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+
+            reader.read_exact(&mut len)?;
+
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+
+    if len > MAX_MESSAGE_BYTES {
+        return Err(invalid("Message too large"));
+    }
+
+    // Servers must not mask, but unmasking costs nothing
+    let mut mask = [0; 4];
+
+    let masked = head[1] & 0x80 != 0;
+
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+
+    let mut payload = vec![0; len as usize];
+
+    reader.read_exact(&mut payload)?;
+
+    if masked {
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+    }
+
+    Ok(Frame {
+        fin: head[0] & 0x80 != 0,
+        opcode: head[0] & 0x0f,
+        payload,
+    })
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// What a line typed in a session sends: text, unless it is one of the commands
+/// `/ping [text]`, `/binary <hex>` or `/close [code [reason]]`; `//` sends a line
+/// starting with `/`
+fn parse_line(line: &str) -> Result<Message, String> {
+    if let Some(text) = line.strip_prefix("//") {
+        return Ok(Message::Text(format!("/{text}")));
+    }
+
+    let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+
+    match command {
+        "/ping" => Ok(Message::Ping(argument.as_bytes().to_vec())),
+        "/binary" => {
+            let hex = argument.split_whitespace().collect::<String>();
+
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|start| {
+                    hex.get(start..start + 2)
+                        .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                })
+                .collect::<Option<Vec<_>>>();
+
+            bytes
+                .map(Message::Binary)
+                .ok_or_else(|| tr_with("websocket-bad-hex", &[("hex", argument)]))
+        }
+        "/close" => {
+            let (code, reason) = argument.split_once(' ').unwrap_or((argument, ""));
+
+            match code {
+                "" => Ok(Message::Close(Some((NORMAL_CLOSURE, String::new())))),
+                code => code
+                    .parse::<u16>()
+                    .map(|code| Message::Close(Some((code, reason.to_string()))))
+                    .map_err(|_| tr_with("websocket-bad-code", &[("code", code)])),
+            }
+        }
+        _ => Ok(Message::Text(line.to_string())),
+    }
+}
+
+/// Sends each line typed on stdin and prints each message as it arrives, until either side
+/// closes the connection or stdin ends
+pub fn session(connection: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, lines) = mpsc::channel();
+
+    // Reading stdin blocks, so it gets a thread of its own
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    eprintln!("{}", tr("websocket-connected"));
+
+    loop {
+        if connection.poll(POLL_INTERVAL)? {
+            match receive(connection)? {
+                Message::Text(text) => println!("{text}"),
+                Message::Binary(bytes) => println!("{}", describe_binary(&bytes)),
+                Message::Ping(payload) => send(connection, &Message::Pong(payload))?,
+                Message::Pong(_) => println!("{}", tr("websocket-pong")),
+                Message::Close(status) => {
+                    // Echoing the status completes the closing handshake
+                    send(connection, &Message::Close(status.clone()))?;
+
+                    eprintln!("{}", describe_close(status.as_ref()));
+
+                    return Ok(());
+                }
+            }
+
+            continue;
+        }
+
+        let message = match lines.try_recv() {
+            Ok(line) => match parse_line(&line) {
+                Ok(message) => message,
+                Err(reason) => {
+                    eprintln!("{reason}");
+
+                    continue;
+                }
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Disconnected) => {
+                Message::Close(Some((NORMAL_CLOSURE, String::new())))
+            }
+        };
+
+        let closing = matches!(message, Message::Close(_));
+
+        send(connection, &message)?;
+
+        if closing {
+            return close(connection);
+        }
+    }
+}
+
+/// Waits for the server to answer a close, skipping whatever it still had on the way
+fn close(connection: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
+    while connection.poll(CLOSE_TIMEOUT)? {
+        match receive(connection)? {
+            Message::Close(status) => {
+                eprintln!("{}", describe_close(status.as_ref()));
+
+                break;
+            }
+            Message::Text(text) => println!("{text}"),
+            Message::Binary(bytes) => println!("{}", describe_binary(&bytes)),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Binary messages in hex, since they may not be printable
+fn describe_binary(bytes: &[u8]) -> String {
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    tr_with(
+        "websocket-binary",
+        &[("bytes", &bytes.len().to_string()), ("hex", &hex)],
+    )
+}
+
+fn describe_close(status: Option<&(u16, String)>) -> String {
+    match status {
+        Some((code, reason)) if !reason.is_empty() => tr_with(
+            "websocket-closed-reason",
+            &[("code", &code.to_string()), ("reason", reason)],
+        ),
+        Some((code, _)) => tr_with("websocket-closed-code", &[("code", &code.to_string())]),
+        None => tr("websocket-closed"),
+    }
+}
+
+/// SHA-1 (RFC 3174), which the WebSocket handshake is fixed to
+fn sha1(input: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    let mut message = input.to_vec();
+
+    message.push(0x80);
+
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+
+    message.extend(((input.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words: Vec<u32> = block
+            .chunks(4)
+            .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+
+        for i in 16..80 {
+            words
+                .push((words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1));
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a827999),
+                1 => (b ^ c ^ d, 0x6ed9eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+
+    for (chunk, value) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_handshake_keys() {
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            hex(&sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(&sha1(&[b'a'; 100])),
+            "7f9000257a4918d7072655ea468540cdcbd42e0c"
+        );
+
+        // The example from RFC 6455, section 1.3
+        assert_eq!(
+            accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+
+        assert_eq!(key().len(), 24);
+        assert!(is_websocket("WSS://example.org/chat"));
+        assert!(!is_websocket("https://example.org/"));
+        assert_eq!(
+            http_url("wss://example.org/chat"),
+            "https://example.org/chat"
+        );
+    }
+
+    #[test]
+    fn frame_messages() {
+        let mut wire = vec![];
+
+        for message in [
+            Message::Text("Hello".to_string()),
+            Message::Binary(vec![7; 300]),
+            Message::Close(Some((1001, "Going away".to_string()))),
+        ] {
+            send(&mut wire, &message).unwrap();
+        }
+
+        // Masked, with the length spelled out in two more bytes past 125
+        assert_eq!(&wire[..2], [0x81, 0x85]);
+        assert_eq!(&wire[11..15], [0x82, 0xfe, 0x01, 0x2c]);
+
+        let mut reader = &wire[..];
+
+        assert_eq!(
+            receive(&mut reader).unwrap(),
+            Message::Text("Hello".to_string())
+        );
+        assert_eq!(receive(&mut reader).unwrap(), Message::Binary(vec![7; 300]));
+        assert_eq!(
+            receive(&mut reader).unwrap(),
+            Message::Close(Some((1001, "Going away".to_string())))
+        );
+
+        // Unmasked from a server: "Hel" and "lo" in two fragments, then an empty ping
+        let mut reader = &b"\x01\x03Hel\x80\x02lo\x89\x00"[..];
+
+        assert_eq!(
+            receive(&mut reader).unwrap(),
+            Message::Text("Hello".to_string())
+        );
+        assert_eq!(receive(&mut reader).unwrap(), Message::Ping(vec![]));
+    }
+
+    #[test]
+    fn parse_typed_lines() {
+        assert_eq!(
+            parse_line("hello /ping").unwrap(),
+            Message::Text("hello /ping".to_string())
+        );
+        assert_eq!(
+            parse_line("//ping").unwrap(),
+            Message::Text("/ping".to_string())
+        );
+        assert_eq!(
+            parse_line("/ping are you there").unwrap(),
+            Message::Ping(b"are you there".to_vec())
+        );
+        assert_eq!(
+            parse_line("/binary 48 65 6c").unwrap(),
+            Message::Binary(b"Hel".to_vec())
+        );
+        assert_eq!(
+            parse_line("/close").unwrap(),
+            Message::Close(Some((1000, String::new())))
+        );
+        assert_eq!(
+            parse_line("/close 4000 bye now").unwrap(),
+            Message::Close(Some((4000, "bye now".to_string())))
+        );
+
+        assert!(parse_line("/binary 4").is_err());
+        assert!(parse_line("/binary zz").is_err());
+        assert!(parse_line("/close soon").is_err());
+    }
+}
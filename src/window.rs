@@ -0,0 +1,99 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+use crate::entity::EntityParser;
+
+/// Titles beyond this many characters are cut, as no tab or taskbar shows more
+const MAX_TITLE_CHARS: usize = 120;
+
+/// Whether the terminal on stderr can be sent escape sequences, leaving piped pages clean
+fn is_terminal() -> bool {
+    io::stderr().is_terminal() && env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+/// Names the terminal window or tab after the page, through OSC 0
+pub fn set_title(title: &str) {
+    if !is_terminal() {
+        return;
+    }
+
+    // A control character in a page title could end the sequence early and inject another
+    let title = title
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .take(MAX_TITLE_CHARS)
+        .collect::<String>();
+
+    eprint!("\x1b]0;{title}\x07");
+
+    let _ = io::stderr().flush();
+}
+
+/// Shows download progress on the taskbar or dock icon, through the OSC 9;4 sequence of
+/// Windows Terminal, also understood by ConEmu, WezTerm and Ghostty; `None` when the length
+/// is unknown
+pub fn set_progress(percent: Option<u64>) {
+    if !is_terminal() {
+        return;
+    }
+
+    match percent {
+        Some(percent) => eprint!("\x1b]9;4;1;{}\x07", percent.min(100)),
+        None => eprint!("\x1b]9;4;3\x07"),
+    }
+
+    let _ = io::stderr().flush();
+}
+
+/// Takes the progress indicator off the taskbar
+pub fn clear_progress() {
+    if is_terminal() {
+        eprint!("\x1b]9;4;0\x07");
+    }
+}
+
+/// The text of the page's `<title>`, with entities decoded and whitespace collapsed
+pub fn page_title(body: &str, entity_parser: &EntityParser) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+
+    let start = lower.find("<title")?;
+
+    // Not `<titles>` or the like
+    if !lower[start + 6..].starts_with(|ch: char| ch == '>' || ch.is_whitespace()) {
+        return None;
+    }
+
+    let open_end = start + lower[start..].find('>')? + 1;
+
+    let end = lower[open_end..]
+        .find("</title")
+        .map_or(lower.len(), |end| open_end + end);
+
+    let title = entity_parser.decode(&body[open_end..end]);
+
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    Some(title).filter(|title| !title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_page_titles() {
+        let entity_parser = EntityParser::new();
+
+        assert_eq!(
+            page_title(
+                "<html><head><TITLE lang=en>\n  Rust &amp; friends\n</TITLE></head>",
+                &entity_parser
+            ),
+            Some("Rust & friends".to_string())
+        );
+
+        assert_eq!(page_title("<title> </title>", &entity_parser), None);
+        assert_eq!(page_title("<titles>No</titles>", &entity_parser), None);
+        assert_eq!(page_title("<p>No title</p>", &entity_parser), None);
+    }
+}